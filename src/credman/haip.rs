@@ -0,0 +1,191 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use serde_json::Value;
+
+use crate::dcql::{algorithm_name, models::VpFormat, parsers::CONFIG};
+
+use super::OpenID4VPRequest;
+
+const ALLOWED_FORMATS: [&str; 2] = ["dc+sd-jwt", "mso_mdoc"];
+const ALLOWED_CLIENT_ID_PREFIXES: [&str; 2] = ["x509_san_dns:", "x509_hash:"];
+const ALLOWED_ALGORITHM: &str = "ES256";
+
+#[derive(Debug)]
+pub enum HaipViolation {
+    UnsignedRequest,
+    UnsupportedClientIdPrefix,
+    UnsupportedAlgorithm(String),
+    UnencryptedResponse,
+    UnsupportedFormat(String),
+}
+impl Display for HaipViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HaipViolation::UnsignedRequest => f.write_str("haip: request is not signed"),
+            HaipViolation::UnsupportedClientIdPrefix => {
+                f.write_str("haip: client_id is not x509_san_dns or x509_hash")
+            }
+            HaipViolation::UnsupportedAlgorithm(alg) => {
+                write!(f, "haip: unsupported algorithm {alg}")
+            }
+            HaipViolation::UnencryptedResponse => f.write_str("haip: response is not encrypted"),
+            HaipViolation::UnsupportedFormat(format) => {
+                write!(f, "haip: unsupported format {format}")
+            }
+        }
+    }
+}
+
+/// HAIP is either compiled in with the `haip` feature or switched on in the wallet config.
+pub fn is_enforced() -> bool {
+    cfg!(feature = "haip") || CONFIG.get().map(|a| a.haip).unwrap_or(false)
+}
+
+/// Rejects requests that are not allowed under the profile. `header` is the JOSE
+/// header of the signed request, if there was one.
+pub(super) fn check(
+    header: Option<&Value>,
+    request: &OpenID4VPRequest,
+) -> Result<(), HaipViolation> {
+    let Some(header) = header else {
+        return Err(HaipViolation::UnsignedRequest);
+    };
+    let alg = header["alg"].as_str().unwrap_or_default();
+    if alg != ALLOWED_ALGORITHM {
+        return Err(HaipViolation::UnsupportedAlgorithm(alg.to_string()));
+    }
+    let client_id = request.client_id.as_deref().unwrap_or_default();
+    if !ALLOWED_CLIENT_ID_PREFIXES
+        .iter()
+        .any(|a| client_id.starts_with(a))
+    {
+        return Err(HaipViolation::UnsupportedClientIdPrefix);
+    }
+    if request.response_mode.as_deref() != Some("dc_api.jwt") {
+        return Err(HaipViolation::UnencryptedResponse);
+    }
+    for credential_query in request.dcql_query.credentials.iter().flatten() {
+        if !ALLOWED_FORMATS.contains(&credential_query.format.as_str()) {
+            return Err(HaipViolation::UnsupportedFormat(
+                credential_query.format.clone(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn restrict(values: &mut Option<Vec<Value>>) {
+    let allowed = match values {
+        Some(values) => values
+            .iter()
+            .filter(|a| algorithm_name(a).as_deref() == Some(ALLOWED_ALGORITHM))
+            .cloned()
+            .collect(),
+        None => vec![Value::from(ALLOWED_ALGORITHM)],
+    };
+    *values = Some(allowed);
+}
+
+/// Credentials have to state their issuer algorithm, and it as well as a stated
+/// holder algorithm have to be ES256.
+pub fn accepts_credential(issuer_alg: Option<&str>, holder_alg: Option<&str>) -> bool {
+    issuer_alg == Some(ALLOWED_ALGORITHM) && holder_alg.is_none_or(|a| a == ALLOWED_ALGORITHM)
+}
+
+/// Narrows the algorithms negotiated with the verifier down to ES256.
+pub fn restrict_algorithms(vp_formats_supported: &mut BTreeMap<String, VpFormat>) {
+    for format in ALLOWED_FORMATS {
+        let vp_format = vp_formats_supported.entry(format.to_string()).or_default();
+        if format == "mso_mdoc" {
+            restrict(&mut vp_format.issuerauth_alg_values);
+            restrict(&mut vp_format.deviceauth_alg_values);
+        } else {
+            restrict(&mut vp_format.sd_jwt_alg_values);
+            restrict(&mut vp_format.kb_jwt_alg_values);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::{accepts_credential, check, restrict_algorithms, HaipViolation};
+    use crate::credman::OpenID4VPRequest;
+
+    #[test]
+    fn test_haip_check() {
+        let mut request = serde_json::from_value::<OpenID4VPRequest>(json!({
+            "client_id": "x509_san_dns:verifier.example.org",
+            "response_mode": "dc_api.jwt",
+            "dcql_query": {
+                "credentials": [{ "id": "pid", "format": "dc+sd-jwt" }]
+            }
+        }))
+        .unwrap();
+        let header = json!({ "alg": "ES256", "x5c": [] });
+        assert!(check(Some(&header), &request).is_ok());
+        assert!(matches!(
+            check(None, &request),
+            Err(HaipViolation::UnsignedRequest)
+        ));
+        assert!(matches!(
+            check(Some(&json!({ "alg": "RS256" })), &request),
+            Err(HaipViolation::UnsupportedAlgorithm(_))
+        ));
+
+        request.response_mode = Some(String::from("dc_api"));
+        assert!(matches!(
+            check(Some(&header), &request),
+            Err(HaipViolation::UnencryptedResponse)
+        ));
+        request.client_id = Some(String::from("redirect_uri:https://verifier.example.org"));
+        assert!(matches!(
+            check(Some(&header), &request),
+            Err(HaipViolation::UnsupportedClientIdPrefix)
+        ));
+    }
+    #[test]
+    fn test_restrict_algorithms() {
+        let mut vp_formats_supported = serde_json::from_value::<BTreeMap<_, _>>(json!({
+            "mso_mdoc": { "issuerauth_alg_values": [-7, -35] }
+        }))
+        .unwrap();
+        restrict_algorithms(&mut vp_formats_supported);
+        assert_eq!(
+            vp_formats_supported["mso_mdoc"].issuerauth_alg_values,
+            Some(vec![json!(-7)])
+        );
+        assert_eq!(
+            vp_formats_supported["dc+sd-jwt"].sd_jwt_alg_values,
+            Some(vec![json!("ES256")])
+        );
+        assert!(accepts_credential(Some("ES256"), None));
+        assert!(!accepts_credential(None, Some("ES256")));
+        assert!(!accepts_credential(Some("ES256"), Some("ES384")));
+    }
+}
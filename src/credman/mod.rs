@@ -17,6 +17,8 @@ KIND, either express or implied.  See the License for the
 specific language governing permissions and limitations
 under the License.
  */
+pub(crate) mod haip;
+
 use std::{any::Any, ffi::CString};
 
 use base64::Engine;
@@ -24,8 +26,10 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use crate::dcql::{
-    models::{Credential, DcqlQuery, Pointer},
-    parsers::{CMWalletDatabaseFormat, ParseCredential, ResultFormat, DEBUG},
+    i18n::{self, localized, Message},
+    models::{ClientMetadata, Credential, DcqlQuery, Pointer, Selection, TransactionData},
+    parsers::{CMWalletDatabaseFormat, ParseCredential, Parser, ResultFormat, CONFIG, DEBUG},
+    preview::PreviewRequest,
 };
 
 #[link(wasm_import_module = "credman")]
@@ -75,6 +79,35 @@ unsafe extern "C" {
         payment_provider_icon: *mut ::std::os::raw::c_char,
         payment_provider_icon_len: usize,
     );
+    #[cfg(feature = "entrysets")]
+    fn AddEntryToSet(
+        cred_id: *const ::std::os::raw::c_char,
+        icon: *const ::std::os::raw::c_char,
+        icon_len: usize,
+        title: *const ::std::os::raw::c_char,
+        subtitle: *const ::std::os::raw::c_char,
+        disclaimer: *const ::std::os::raw::c_char,
+        warning: *const ::std::os::raw::c_char,
+        metadata: *const ::std::os::raw::c_char,
+        set_id: *const ::std::os::raw::c_char,
+        set_index: ::std::os::raw::c_int,
+    );
+    #[cfg(feature = "entrysets")]
+    fn AddFieldToEntrySet(
+        cred_id: *const ::std::os::raw::c_char,
+        field_display_name: *const ::std::os::raw::c_char,
+        field_display_value: *const ::std::os::raw::c_char,
+        set_id: *const ::std::os::raw::c_char,
+        set_index: ::std::os::raw::c_int,
+    );
+    #[cfg(feature = "inlineissuance")]
+    fn AddInlineIssuanceEntry(
+        cred_id: *const ::std::os::raw::c_char,
+        icon: *const ::std::os::raw::c_char,
+        icon_len: usize,
+        title: *const ::std::os::raw::c_char,
+        subtitle: *const ::std::os::raw::c_char,
+    );
     #[link_name = "GetCredentialsSize"]
     fn GetCredentialsSize(size: *mut u32);
     #[link_name = "ReadCredentialsBuffer"]
@@ -108,6 +141,7 @@ pub fn get_credentials(parser: &dyn ParseCredential) -> Vec<Credential> {
         return vec![];
     };
     parser.set_debug(json_str);
+    parser.set_config(json_str);
     let Some(result) = parser.parse(json_str) else {
         return_error("invalid credential format");
         return vec![];
@@ -119,10 +153,13 @@ pub fn get_credentials(parser: &dyn ParseCredential) -> Vec<Credential> {
 pub fn select_credential(
     c: Credential,
     attributes: Vec<(Pointer, String)>,
-    provider_index: usize,
-    result_format: &dyn ResultFormat,
+    transaction_fields: &[(String, String)],
+    disclaimer: Option<&str>,
+    warning: Option<&str>,
+    selection: &Selection,
+    result_format: &dyn Parser,
 ) {
-    let display_data = c.get_display_metadata();
+    let display_data = c.get_display_metadata(result_format.locale().as_deref());
 
     let Ok(title) = CString::new(display_data.title) else {
         return;
@@ -130,29 +167,13 @@ pub fn select_credential(
     let Ok(subtitle) = CString::new(display_data.subtitle) else {
         return;
     };
-    let id = result_format.id(&display_data.id, provider_index);
+    let id = result_format.id(&display_data.id, selection);
     let Ok(id) = CString::new(id) else {
         return;
     };
-    let mut credentials_size: u32 = 0;
-    unsafe {
-        GetCredentialsSize(&mut credentials_size as *mut u32);
-    };
-
-    let mut buffer = vec![0u8; credentials_size as usize];
-    unsafe {
-        ReadCredentialsBuffer(buffer.as_mut_ptr(), 0, buffer.len());
-    };
-    let mut icon = std::ptr::null();
-    let mut icon_len = 0;
-    if let Some(_) = (result_format as &dyn Any).downcast_ref::<CMWalletDatabaseFormat>() {
-        let start = display_data.icon["start"].as_i64().unwrap_or(0) as usize;
-        let length = display_data.icon["length"].as_i64().unwrap_or(0) as usize;
-        let icon_slice = buffer[start..start + length].to_vec();
-        icon = icon_slice.as_ptr() as *const i8;
-        icon_len = icon_slice.len();
-        std::mem::forget(icon_slice);
-    }
+    let disclaimer = disclaimer.and_then(|a| CString::new(a).ok());
+    let warning = warning.and_then(|a| CString::new(a).ok());
+    let (icon, icon_len) = entry_icon(&display_data.icon, result_format);
 
     unsafe {
         AddStringIdEntry(
@@ -161,32 +182,243 @@ pub fn select_credential(
             icon_len,
             title.as_ptr(),
             subtitle.as_ptr(),
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
+            disclaimer.as_ref().map_or(std::ptr::null(), |a| a.as_ptr()),
+            warning.as_ref().map_or(std::ptr::null(), |a| a.as_ptr()),
         );
-        if attributes.is_empty() {
-            AddFieldForStringIdEntry(id.as_ptr(), c"<nothing>".as_ptr(), std::ptr::null());
+        for (name, value) in entry_fields(&c, attributes, transaction_fields, result_format) {
+            let value = value.as_ref().map_or(std::ptr::null(), |a| a.as_ptr());
+            AddFieldForStringIdEntry(id.as_ptr(), name.as_ptr(), value);
         }
-        for (ptr, a) in attributes {
-            let claims = c.get_claims();
-            let display_name = result_format.get_display_name(&ptr, &claims).unwrap_or(a);
-            let display_value = result_format
-                .get_value(&ptr, &claims)
-                .map(|a| CString::new(a).ok())
-                .flatten();
+    }
+}
+
+/// The icon of an entry, stored in the credentials buffer for the cmwallet format
+fn entry_icon(icon: &Value, result_format: &dyn Parser) -> (*const i8, usize) {
+    if (result_format as &dyn Any)
+        .downcast_ref::<CMWalletDatabaseFormat>()
+        .is_none()
+    {
+        return (std::ptr::null(), 0);
+    }
+    let mut credentials_size: u32 = 0;
+    unsafe {
+        GetCredentialsSize(&mut credentials_size as *mut u32);
+    };
 
+    let mut buffer = vec![0u8; credentials_size as usize];
+    unsafe {
+        ReadCredentialsBuffer(buffer.as_mut_ptr(), 0, buffer.len());
+    };
+    let start = icon["start"].as_u64().unwrap_or(0) as usize;
+    let length = icon["length"].as_u64().unwrap_or(0) as usize;
+    let Some(icon_slice) = start
+        .checked_add(length)
+        .and_then(|end| buffer.get(start..end))
+        .map(|a| a.to_vec())
+    else {
+        return (std::ptr::null(), 0);
+    };
+    let icon = (icon_slice.as_ptr() as *const i8, icon_slice.len());
+    std::mem::forget(icon_slice);
+    icon
+}
+
+/// Fields of an entry: the presented claims, followed by `extra_fields` like
+/// transaction data
+fn entry_fields(
+    c: &Credential,
+    attributes: Vec<(Pointer, String)>,
+    extra_fields: &[(String, String)],
+    result_format: &dyn Parser,
+) -> Vec<(CString, Option<CString>)> {
+    let mut fields = vec![];
+    if attributes.is_empty() {
+        if let Ok(nothing) = CString::new(result_format.message(Message::Nothing)) {
+            fields.push((nothing, None));
+        }
+    }
+    for (ptr, a) in attributes {
+        for (display_name, display_value) in result_format.get_fields(&ptr, a, c) {
             let Ok(name) = CString::new(display_name) else {
                 continue;
             };
-            let mut val_ptr = std::ptr::null();
-            if let Some(val) = display_value.as_ref() {
-                val_ptr = val.as_ptr();
+            fields.push((name, display_value.and_then(|a| CString::new(a).ok())));
+        }
+    }
+    for (name, value) in extra_fields {
+        let (Ok(name), Ok(value)) = (CString::new(name.as_str()), CString::new(value.as_str()))
+        else {
+            continue;
+        };
+        fields.push((name, Some(value)));
+    }
+    fields
+}
+
+/// A credential of an entry set together with what is presented from it
+#[cfg(feature = "entrysets")]
+pub struct SetMember {
+    pub credential: Credential,
+    pub attributes: Vec<(Pointer, String)>,
+    pub extra_fields: Vec<(String, String)>,
+    pub warning: Option<String>,
+    pub selection: Selection,
+}
+
+/// Adds a single selectable item presenting all `members` together
+#[cfg(feature = "entrysets")]
+pub fn select_credential_set(
+    set_id: &str,
+    members: Vec<SetMember>,
+    disclaimer: Option<&str>,
+    result_format: &dyn Parser,
+) {
+    let Ok(set_id) = CString::new(set_id) else {
+        return;
+    };
+    let disclaimer = disclaimer.and_then(|a| CString::new(a).ok());
+    for (set_index, member) in members.into_iter().enumerate() {
+        let display_data = member
+            .credential
+            .get_display_metadata(result_format.locale().as_deref());
+        let (Ok(title), Ok(subtitle), Ok(id)) = (
+            CString::new(display_data.title),
+            CString::new(display_data.subtitle),
+            CString::new(result_format.id(&display_data.id, &member.selection)),
+        ) else {
+            continue;
+        };
+        let warning = member.warning.and_then(|a| CString::new(a).ok());
+        let (icon, icon_len) = entry_icon(&display_data.icon, result_format);
+        let set_index = set_index as ::std::os::raw::c_int;
+        let fields = entry_fields(
+            &member.credential,
+            member.attributes,
+            &member.extra_fields,
+            result_format,
+        );
+        unsafe {
+            AddEntryToSet(
+                id.as_ptr(),
+                icon,
+                icon_len,
+                title.as_ptr(),
+                subtitle.as_ptr(),
+                disclaimer.as_ref().map_or(std::ptr::null(), |a| a.as_ptr()),
+                warning.as_ref().map_or(std::ptr::null(), |a| a.as_ptr()),
+                std::ptr::null(),
+                set_id.as_ptr(),
+                set_index,
+            );
+            for (name, value) in fields {
+                let value = value.as_ref().map_or(std::ptr::null(), |a| a.as_ptr());
+                AddFieldToEntrySet(
+                    id.as_ptr(),
+                    name.as_ptr(),
+                    value,
+                    set_id.as_ptr(),
+                    set_index,
+                );
             }
-            AddFieldForStringIdEntry(id.as_ptr(), name.as_ptr(), val_ptr);
         }
     }
 }
 
+/// Fields of a credential from an optional credential set, shown after the
+/// fields of the entry and marked as optional
+pub fn optional_fields(
+    c: &Credential,
+    attributes: &[(Pointer, String)],
+    result_format: &dyn Parser,
+) -> Vec<(String, String)> {
+    let optional = result_format.message(Message::Optional);
+    attributes
+        .iter()
+        .flat_map(|(ptr, a)| result_format.get_fields(ptr, a.clone(), c))
+        .map(|(name, value)| (format!("{name} ({optional})"), value.unwrap_or_default()))
+        .collect()
+}
+
+/// Offers to obtain the credentials requested by `query` from an issuer, using the
+/// `issuance_offers` of the wallet config. Returns `false` if there is none.
+#[cfg(feature = "inlineissuance")]
+pub fn offer_issuance(
+    query: &DcqlQuery,
+    credentials: &[Credential],
+    provider_index: usize,
+    result_format: &dyn Parser,
+) -> bool {
+    let offers = CONFIG
+        .get()
+        .map(|a| a.issuance_offers.as_slice())
+        .unwrap_or_default();
+    let offers = query.issuance_offers(offers, credentials);
+    let locale = result_format.locale();
+    for offer in &offers {
+        let id = serde_json::json!({ "provider_idx": provider_index, "issuance": offer.id });
+        let (Ok(id), Ok(title), Ok(subtitle)) = (
+            CString::new(id.to_string()),
+            CString::new(localized(&offer.title, locale.as_deref()).unwrap_or_default()),
+            CString::new(localized(&offer.subtitle, locale.as_deref()).unwrap_or_default()),
+        ) else {
+            continue;
+        };
+        let (icon, icon_len) = entry_icon(&offer.icon, result_format);
+        unsafe {
+            AddInlineIssuanceEntry(
+                id.as_ptr(),
+                icon,
+                icon_len,
+                title.as_ptr(),
+                subtitle.as_ptr(),
+            );
+        }
+    }
+    !offers.is_empty()
+}
+
+/// Why data is requested and by whom: the localized `purpose` of the credential
+/// set and the verifier's name.
+pub fn disclaimer(
+    purpose: Option<&Value>,
+    verifier: Option<&str>,
+    result_format: &dyn ResultFormat,
+) -> Option<String> {
+    let locale = result_format.locale();
+    let purpose = purpose.and_then(|a| localized(a, locale.as_deref()));
+    let verifier = verifier.map(|v| format!("{}: {v}", result_format.message(Message::Verifier)));
+    let lines = [purpose, verifier]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// The verifier as shown to the user: its `client_name`, otherwise the
+/// `client_id` without its client identifier prefix.
+fn verifier_name(client_name: Option<&str>, client_id: Option<&str>) -> Option<String> {
+    if let Some(client_name) = client_name {
+        return Some(client_name.to_string());
+    }
+    let client_id = client_id?;
+    let unprefixed = CLIENT_ID_PREFIXES
+        .iter()
+        .find_map(|prefix| client_id.strip_prefix(prefix))
+        .unwrap_or(client_id);
+    Some(unprefixed.to_string())
+}
+
+const CLIENT_ID_PREFIXES: [&str; 8] = [
+    "redirect_uri:",
+    "x509_san_dns:",
+    "x509_hash:",
+    "decentralized_identifier:",
+    "verifier_attestation:",
+    "openid_federation:",
+    "origin:",
+    "pre-registered:",
+];
+
 #[inline]
 pub fn return_error(title: &str) {
     if !DEBUG.get().unwrap_or(&false) {
@@ -195,17 +427,20 @@ pub fn return_error(title: &str) {
     let Ok(title) = CString::new(title) else {
         return;
     };
+    let Ok(error) = CString::new(i18n::message(Message::Error, i18n::locale().as_deref())) else {
+        return;
+    };
     unsafe {
         AddStringIdEntry(
             c"some_id".as_ptr(),
             std::ptr::null_mut(),
             0,
             title.as_ptr(),
-            c"error".as_ptr(),
+            error.as_ptr(),
             std::ptr::null_mut(),
             std::ptr::null_mut(),
         );
-        AddFieldForStringIdEntry(c"some_id".as_ptr(), c"error".as_ptr(), std::ptr::null());
+        AddFieldForStringIdEntry(c"some_id".as_ptr(), error.as_ptr(), std::ptr::null());
     }
 }
 
@@ -235,21 +470,37 @@ pub fn get_dc_request() -> Option<(usize, DcqlQuery)> {
         return_error(&format!("2 providers empty"));
         return None;
     }
+    // OpenID4VP is preferred over the legacy preview protocol
     let Some(first_provider) = query
         .providers
         .iter()
         .enumerate()
         .filter(|(_, a)| matches!(a, Providers::OpenID4VP(_)))
+        .chain(
+            query
+                .providers
+                .iter()
+                .enumerate()
+                .filter(|(_, a)| matches!(a, Providers::Preview(_))),
+        )
         .next()
     else {
         return_error(&format!("3 no openid4vp provider found"));
         return None;
     };
-    let Providers::OpenID4VP(provider) = first_provider.1 else {
-        return_error(&format!("4 no openid4vp provider found"));
-        return None;
+    let provider = match first_provider.1 {
+        Providers::OpenID4VP(provider) => provider,
+        Providers::Preview(provider) => {
+            return get_preview_request(provider).map(|q| (first_provider.0, q));
+        }
+        Providers::Unknown => {
+            return_error(&format!("4 no openid4vp provider found"));
+            return None;
+        }
     };
 
+    // JOSE header of a signed request
+    let mut header = None;
     let query = match &provider.request {
         Value::Object(_) => {
             let Some(Value::String(wrapped_request)) = provider.request.get("request") else {
@@ -268,6 +519,7 @@ pub fn get_dc_request() -> Option<(usize, DcqlQuery)> {
                 return_error(&format!("!=3 2 base64 decode failed {}", parts[1]));
                 return None;
             }
+            header = decode_header(parts[0]);
             let Ok(q) = serde_json::from_slice::<OpenID4VPRequest>(&claims) else {
                 return_error(&format!(
                     "!=3 3 base64 decode failed {:?}",
@@ -297,6 +549,7 @@ pub fn get_dc_request() -> Option<(usize, DcqlQuery)> {
                     return None;
                 };
                 query = new_query.to_string();
+                header = decode_header(parts[0]);
             }
 
             let Ok(q) = serde_json::from_str::<OpenID4VPRequest>(&query) else {
@@ -310,7 +563,74 @@ pub fn get_dc_request() -> Option<(usize, DcqlQuery)> {
             return None;
         }
     };
-    Some((first_provider.0, query.dcql_query.clone()))
+    let haip_enforced = haip::is_enforced();
+    if haip_enforced {
+        if let Err(violation) = haip::check(header.as_ref(), &query) {
+            return_error(&violation.to_string());
+            return None;
+        }
+    }
+    let mut dcql_query = query.dcql_query;
+    dcql_query.verifier = verifier_name(
+        query
+            .client_metadata
+            .as_ref()
+            .and_then(|a| a.client_name.as_deref()),
+        query.client_id.as_deref(),
+    );
+    dcql_query.client_id = query.client_id.clone();
+    dcql_query.signed = header.is_some();
+    if let Some(locale) = query
+        .ui_locales
+        .as_deref()
+        .and_then(|a| a.split_whitespace().next())
+    {
+        let _ = i18n::REQUEST_LOCALE.set(locale.to_string());
+    }
+    if let Some(client_metadata) = query.client_metadata {
+        let wallet_config = CONFIG.get().cloned().unwrap_or_default();
+        if query.response_mode.as_deref() == Some("dc_api.jwt")
+            && !wallet_config.supports_response_encryption(&client_metadata)
+        {
+            return_error("response encryption not supported");
+            return None;
+        }
+        dcql_query.vp_formats_supported = client_metadata.vp_formats_supported;
+    }
+    if haip_enforced {
+        haip::restrict_algorithms(&mut dcql_query.vp_formats_supported);
+    }
+    for transaction_data in &query.transaction_data {
+        let Ok(transaction_data) = transaction_data.parse::<TransactionData>() else {
+            return_error(&format!("invalid transaction_data {transaction_data}"));
+            return None;
+        };
+        dcql_query.transaction_data.push(transaction_data);
+    }
+    Some((first_provider.0, dcql_query))
+}
+
+fn get_preview_request(provider: &DCRequest) -> Option<DcqlQuery> {
+    if haip::is_enforced() {
+        return_error("haip: preview requests are not signed");
+        return None;
+    }
+    let request = match &provider.request {
+        Value::String(s) => serde_json::from_str::<PreviewRequest>(s),
+        request => serde_json::from_value::<PreviewRequest>(request.clone()),
+    };
+    let Ok(request) = request else {
+        return_error(&format!("invalid preview request {:?}", provider.request));
+        return None;
+    };
+    Some(request.into())
+}
+
+fn decode_header(header: &str) -> Option<Value> {
+    let header = base64::prelude::BASE64_URL_SAFE_NO_PAD
+        .decode(header)
+        .ok()?;
+    serde_json::from_slice(&header).ok()
 }
 
 #[derive(Deserialize)]
@@ -325,6 +645,8 @@ pub enum Providers {
     #[serde(alias = "openid4vp-v1-unsigned")]
     #[serde(alias = "openid4vp-v1-signed")]
     OpenID4VP(DCRequest),
+    #[serde(rename = "preview")]
+    Preview(DCRequest),
     #[serde(other)]
     Unknown,
 }
@@ -336,9 +658,89 @@ pub struct DCRequest {
 #[derive(Deserialize)]
 struct OpenID4VPRequest {
     dcql_query: DcqlQuery,
+    #[serde(default)]
+    transaction_data: Vec<String>,
+    client_metadata: Option<ClientMetadata>,
+    response_mode: Option<String>,
+    client_id: Option<String>,
+    /// Space separated BCP 47 language tags in order of preference
+    ui_locales: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct WrappedRequest {
     request: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{disclaimer, verifier_name, CLIENT_ID_PREFIXES};
+    use crate::dcql::{
+        models::{Credential, PointerPart, Selection},
+        parsers::{CMWalletDatabaseFormat, ResultFormat},
+    };
+
+    struct German;
+
+    impl ResultFormat for German {
+        fn id(&self, credential_id: &str, selection: &Selection) -> String {
+            CMWalletDatabaseFormat.id(credential_id, selection)
+        }
+        fn get_value(&self, path: &[PointerPart], credential: &Credential) -> Option<String> {
+            CMWalletDatabaseFormat.get_value(path, credential)
+        }
+        fn get_display_name(
+            &self,
+            path: &[PointerPart],
+            credential: &Credential,
+        ) -> Option<String> {
+            CMWalletDatabaseFormat.get_display_name(path, credential)
+        }
+        fn locale(&self) -> Option<String> {
+            Some(String::from("de-CH"))
+        }
+    }
+
+    #[test]
+    fn test_verifier_name() {
+        for prefix in CLIENT_ID_PREFIXES {
+            assert_eq!(
+                verifier_name(None, Some(&format!("{prefix}verifier.example.org"))).as_deref(),
+                Some("verifier.example.org")
+            );
+        }
+        assert_eq!(
+            verifier_name(None, Some("https://verifier.example.org")).as_deref(),
+            Some("https://verifier.example.org")
+        );
+        assert_eq!(
+            verifier_name(
+                Some("Example Verifier"),
+                Some("origin:verifier.example.org")
+            )
+            .as_deref(),
+            Some("Example Verifier")
+        );
+        assert_eq!(verifier_name(None, None), None);
+    }
+
+    #[test]
+    fn test_disclaimer() {
+        let purpose = json!({ "en": "Age verification", "de": "Altersnachweis" });
+        assert_eq!(
+            disclaimer(Some(&purpose), Some("Example Verifier"), &German).as_deref(),
+            Some("Altersnachweis\nVerifizierer: Example Verifier")
+        );
+        assert_eq!(
+            disclaimer(Some(&purpose), None, &CMWalletDatabaseFormat).as_deref(),
+            Some("Age verification")
+        );
+        assert_eq!(
+            disclaimer(None, Some("Example Verifier"), &CMWalletDatabaseFormat).as_deref(),
+            Some("Verifier: Example Verifier")
+        );
+        assert_eq!(disclaimer(None, None, &German), None);
+    }
+}
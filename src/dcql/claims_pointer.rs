@@ -34,18 +34,50 @@ impl Display for QueryError {
         f.write_str("{self:?}")
     }
 }
+/// Data model a claims path pointer can select from. Besides JSON this allows
+/// selecting from data models keeping more type information, e.g. CBOR.
+pub trait ClaimsTree: Clone {
+    fn get_key(&self, key: &str) -> Option<&Self>;
+    fn get_index(&self, index: usize) -> Option<&Self>;
+    fn elements(&self) -> Option<Vec<&Self>>;
+    fn is_object(&self) -> bool;
+    fn is_array(&self) -> bool {
+        self.elements().is_some()
+    }
+    /// JSON representation used to compare against the `values` of a claims query
+    fn to_json(&self) -> Value;
+}
+
+impl ClaimsTree for Value {
+    fn get_key(&self, key: &str) -> Option<&Self> {
+        self.get(key)
+    }
+    fn get_index(&self, index: usize) -> Option<&Self> {
+        self.get(index)
+    }
+    fn elements(&self) -> Option<Vec<&Self>> {
+        self.as_array().map(|a| a.iter().collect())
+    }
+    fn is_object(&self) -> bool {
+        self.is_object()
+    }
+    fn to_json(&self) -> Value {
+        self.clone()
+    }
+}
+
 pub trait Selector: Send + Sync {
-    fn select(&self, v: Value) -> Result<Vec<Value>, QueryError>;
-    fn resolve_ptr(&self, v: Value) -> Result<Vec<Pointer>, QueryError>;
+    fn select<V: ClaimsTree>(&self, v: V) -> Result<Vec<V>, QueryError>;
+    fn resolve_ptr<V: ClaimsTree>(&self, v: V) -> Result<Vec<Pointer>, QueryError>;
 }
 
 impl Selector for Pointer {
-    fn select(&self, v: Value) -> Result<Vec<Value>, QueryError> {
+    fn select<V: ClaimsTree>(&self, v: V) -> Result<Vec<V>, QueryError> {
         let s = selector(self);
         s(&v)
     }
 
-    fn resolve_ptr(&self, v: Value) -> Result<Vec<Pointer>, QueryError> {
+    fn resolve_ptr<V: ClaimsTree>(&self, v: V) -> Result<Vec<Pointer>, QueryError> {
         let mut current_pointers = vec![vec![]];
         let mut the_pointer = vec![];
         for p in self {
@@ -55,10 +87,10 @@ impl Selector for Pointer {
                     if element.len() > 1 || element.is_empty() {
                         return Ok(vec![]);
                     }
-                    if !element[0].is_array() {
+                    let Some(elements) = element[0].elements() else {
                         return Ok(vec![]);
-                    }
-                    let element_size = element[0].as_array().unwrap_or(&Vec::new()).len();
+                    };
+                    let element_size = elements.len();
                     let mut new_pointers = vec![];
                     for ptrs in &current_pointers {
                         for i in 0..element_size {
@@ -82,9 +114,9 @@ impl Selector for Pointer {
     }
 }
 
-pub fn selector<'a, T: AsRef<[PointerPart]> + 'a>(
+pub fn selector<'a, V: ClaimsTree + 'a, T: AsRef<[PointerPart]> + 'a>(
     path: T,
-) -> impl Fn(&'a Value) -> Result<Vec<Value>, QueryError> {
+) -> impl Fn(&'a V) -> Result<Vec<V>, QueryError> {
     move |input| {
         let mut currently_selected = vec![input.clone()];
         for part in path.as_ref() {
@@ -92,21 +124,21 @@ pub fn selector<'a, T: AsRef<[PointerPart]> + 'a>(
                 PointerPart::String(key) if currently_selected.iter().all(|a| a.is_object()) => {
                     currently_selected = currently_selected
                         .iter()
-                        .flat_map(|a| a.get(key))
+                        .flat_map(|a| a.get_key(key))
                         .cloned()
                         .collect()
                 }
                 PointerPart::Index(i) if currently_selected.iter().all(|a| a.is_array()) => {
                     currently_selected = currently_selected
                         .iter()
-                        .flat_map(|a| a.get(*i as usize))
+                        .flat_map(|a| a.get_index(*i as usize))
                         .cloned()
                         .collect()
                 }
                 PointerPart::Null(_) if currently_selected.iter().all(|a| a.is_array()) => {
                     currently_selected = currently_selected
                         .iter()
-                        .filter_map(|a| a.as_array())
+                        .filter_map(|a| a.elements())
                         .flatten()
                         .cloned()
                         .collect()
@@ -0,0 +1,197 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+//! Combinations of credentials presented together, e.g. a PID and an mDL
+//! requested by two required credential sets.
+
+use serde_json::Value;
+
+use super::models::{CredentialSetOption, Disclosure};
+
+/// Upper bound of combinations offered, the best ranked ones are kept
+pub const MAX_COMBINATIONS: usize = 16;
+
+/// A credential presented as part of a combination
+#[derive(Debug, Clone)]
+pub struct Presentation<'a> {
+    /// Index of the credential set in the query's `credential_sets`
+    pub credential_set: usize,
+    pub required: bool,
+    /// `id` of the credential query
+    pub credential_query: &'a str,
+    pub disclosure: &'a Disclosure,
+}
+
+impl Presentation<'_> {
+    /// `{"credential_set": 0, "credential_query": "pid", "id": "1"}`, with the
+    /// `claim_set` if there is one
+    pub fn to_json(&self) -> Value {
+        let mut json = serde_json::json!({
+            "credential_set": self.credential_set,
+            "credential_query": self.credential_query,
+            "id": self.disclosure.credential.get_display_metadata(None).id
+        });
+        if let Some(claim_set) = self.disclosure.claim_set {
+            json["claim_set"] = Value::from(claim_set);
+        }
+        json
+    }
+}
+
+/// The best ranked credential for every credential query of the first option
+/// of an optional set, `None` if one of them has no credential
+fn optional_presentations(set: &CredentialSetOption) -> Option<Vec<Presentation<'_>>> {
+    set.set_options
+        .first()?
+        .iter()
+        .map(|query| {
+            Some(Presentation {
+                credential_set: set.index,
+                required: false,
+                credential_query: &query.id,
+                disclosure: query.options.first()?,
+            })
+        })
+        .collect()
+}
+
+/// All ways to satisfy the required credential sets, taking one option of each
+/// set and one credential for each of its credential queries. Every combination
+/// is also offered together with each optional set, room for these variants is
+/// kept by offering fewer required combinations.
+pub fn combinations(options: &[CredentialSetOption]) -> Vec<Vec<Presentation<'_>>> {
+    let optional = options
+        .iter()
+        .filter(|a| !a.required)
+        .filter_map(optional_presentations)
+        .collect::<Vec<_>>();
+    let max_required = (MAX_COMBINATIONS / (optional.len() + 1)).max(1);
+    let mut combinations: Vec<Vec<Presentation>> = vec![vec![]];
+    for set in options.iter().filter(|a| a.required) {
+        let mut extended = vec![];
+        for combination in &combinations {
+            for set_option in &set.set_options {
+                let mut partial = vec![combination.clone()];
+                for query in set_option {
+                    partial = partial
+                        .iter()
+                        .flat_map(|p| {
+                            query.options.iter().map(|disclosure| {
+                                let mut p = p.clone();
+                                p.push(Presentation {
+                                    credential_set: set.index,
+                                    required: true,
+                                    credential_query: &query.id,
+                                    disclosure,
+                                });
+                                p
+                            })
+                        })
+                        .take(max_required)
+                        .collect();
+                }
+                extended.extend(partial);
+            }
+        }
+        combinations = extended.into_iter().take(max_required).collect();
+    }
+    let required = combinations.clone();
+    for presentations in &optional {
+        combinations.extend(
+            required
+                .iter()
+                .map(|a| [a.as_slice(), presentations].concat()),
+        );
+    }
+    combinations.retain(|a| !a.is_empty());
+    combinations.truncate(MAX_COMBINATIONS);
+    combinations
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{combinations, MAX_COMBINATIONS};
+    use crate::dcql::models::{Credential, DcqlQuery};
+
+    #[test]
+    fn test_combinations() {
+        let query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [
+                { "id": "pid", "format": "dc+sd-jwt", "meta": { "vct_values": ["pid"] } },
+                { "id": "mdl", "format": "mso_mdoc", "meta": { "doctype_value": "mdl" } },
+                { "id": "loyalty", "format": "dc+sd-jwt", "meta": { "vct_values": ["loyalty"] } }
+            ],
+            "credential_sets": [
+                { "options": [["pid"]] },
+                { "options": [["mdl"]] },
+                { "options": [["loyalty"]], "required": false }
+            ]
+        }))
+        .unwrap();
+        let credential = |id: &str, document_type: &str, format: &str| {
+            Credential::DummyCredential(json!({
+                "id": id,
+                "document_type": document_type,
+                "credential_format": format
+            }))
+        };
+        let credentials = vec![
+            credential("1", "pid", "dc+sd-jwt"),
+            credential("2", "pid", "dc+sd-jwt"),
+            credential("3", "mdl", "mso_mdoc"),
+            credential("4", "loyalty", "dc+sd-jwt"),
+        ];
+        let options = query.select_credentials(credentials);
+        let ids = combinations(&options)
+            .iter()
+            .map(|a| {
+                a.iter()
+                    .map(|a| a.disclosure.credential.get_display_metadata(None).id)
+                    .collect::<Vec<_>>()
+                    .join("+")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1+3", "2+3", "1+3+4", "2+3+4"]);
+        assert_eq!(
+            combinations(&options)[2][2].to_json(),
+            json!({ "credential_set": 2, "credential_query": "loyalty", "id": "4" })
+        );
+
+        // optional variants are kept when the required combinations exceed the limit
+        let credentials = (0..MAX_COMBINATIONS)
+            .map(|i| credential(&format!("pid{i}"), "pid", "dc+sd-jwt"))
+            .chain([
+                credential("mdl", "mdl", "mso_mdoc"),
+                credential("loyalty", "loyalty", "dc+sd-jwt"),
+            ])
+            .collect::<Vec<_>>();
+        let options = query.select_credentials(credentials);
+        let combinations = combinations(&options);
+        assert_eq!(combinations.len(), MAX_COMBINATIONS);
+        assert_eq!(
+            combinations
+                .iter()
+                .filter(|a| a.iter().any(|p| !p.required))
+                .count(),
+            MAX_COMBINATIONS / 2
+        );
+    }
+}
@@ -0,0 +1,223 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use super::{
+    parsers::CONFIG,
+    type_metadata::{select_label, ClaimDisplay},
+};
+
+/// Languages of the translation tables, in column order
+pub const LANGUAGES: [&str; 4] = ["en", "de", "fr", "it"];
+
+/// First of the `ui_locales` of the request
+pub static REQUEST_LOCALE: OnceLock<String> = OnceLock::new();
+
+/// Strings produced by the matcher itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    Nothing,
+    Yes,
+    No,
+    Error,
+    Verifier,
+    Optional,
+    SensitiveRequest,
+    UnsignedRequest,
+    UnknownVerifier,
+    IntentToRetain,
+    Image,
+    Bytes,
+    SexNotKnown,
+    SexMale,
+    SexFemale,
+    SexNotApplicable,
+    Document,
+    DocumentHash,
+    HashAlgorithm,
+    SignatureQualifier,
+    SigningCredential,
+    TermsAndConditions,
+    TermsHash,
+}
+
+impl Message {
+    fn translations(self) -> [&'static str; 4] {
+        match self {
+            Message::Nothing => [
+                "No data requested",
+                "Keine Daten angefragt",
+                "Aucune donnée demandée",
+                "Nessun dato richiesto",
+            ],
+            Message::Yes => ["Yes", "Ja", "Oui", "Sì"],
+            Message::No => ["No", "Nein", "Non", "No"],
+            Message::Error => ["Error", "Fehler", "Erreur", "Errore"],
+            Message::SensitiveRequest => [
+                "This request asks for sensitive data.",
+                "Diese Anfrage verlangt sensible Daten.",
+                "Cette demande porte sur des données sensibles.",
+                "Questa richiesta riguarda dati sensibili.",
+            ],
+            Message::UnsignedRequest => [
+                "The request is not signed by the verifier.",
+                "Die Anfrage ist nicht vom Verifizierer signiert.",
+                "La demande n'est pas signée par le vérificateur.",
+                "La richiesta non è firmata dal verificatore.",
+            ],
+            Message::UnknownVerifier => [
+                "The verifier is not known to your wallet.",
+                "Der Verifizierer ist Ihrem Wallet nicht bekannt.",
+                "Le vérificateur n'est pas connu de votre wallet.",
+                "Il verificatore non è noto al tuo wallet.",
+            ],
+            Message::IntentToRetain => [
+                "The verifier intends to retain the data.",
+                "Der Verifizierer beabsichtigt, die Daten zu speichern.",
+                "Le vérificateur a l'intention de conserver les données.",
+                "Il verificatore intende conservare i dati.",
+            ],
+            Message::Verifier => ["Verifier", "Verifizierer", "Vérificateur", "Verificatore"],
+            Message::Optional => ["optional", "optional", "facultatif", "facoltativo"],
+            Message::Image => ["Image", "Bild", "Image", "Immagine"],
+            Message::Bytes => ["bytes", "Bytes", "octets", "byte"],
+            Message::SexNotKnown => ["Not known", "Unbekannt", "Inconnu", "Sconosciuto"],
+            Message::SexMale => ["Male", "Männlich", "Masculin", "Maschile"],
+            Message::SexFemale => ["Female", "Weiblich", "Féminin", "Femminile"],
+            Message::SexNotApplicable => [
+                "Not applicable",
+                "Nicht anwendbar",
+                "Non applicable",
+                "Non applicabile",
+            ],
+            Message::Document => ["Document", "Dokument", "Document", "Documento"],
+            Message::DocumentHash => [
+                "Document hash",
+                "Dokument-Hash",
+                "Empreinte du document",
+                "Hash del documento",
+            ],
+            Message::HashAlgorithm => [
+                "Hash algorithm",
+                "Hash-Algorithmus",
+                "Algorithme de hachage",
+                "Algoritmo di hash",
+            ],
+            Message::SignatureQualifier => [
+                "Signature qualifier",
+                "Signaturqualifikation",
+                "Qualification de la signature",
+                "Qualificazione della firma",
+            ],
+            Message::SigningCredential => [
+                "Signing credential",
+                "Signaturzertifikat",
+                "Certificat de signature",
+                "Certificato di firma",
+            ],
+            Message::TermsAndConditions => [
+                "Terms and conditions",
+                "Nutzungsbedingungen",
+                "Conditions générales",
+                "Condizioni generali",
+            ],
+            Message::TermsHash => [
+                "Terms hash",
+                "Hash der Bedingungen",
+                "Empreinte des conditions",
+                "Hash delle condizioni",
+            ],
+        }
+    }
+}
+
+/// Column of `locale` in the translation tables, English if the language is unknown
+pub fn language_index(locale: Option<&str>) -> usize {
+    let language = locale
+        .and_then(|l| l.split(['-', '_']).next())
+        .map(|l| l.to_lowercase());
+    LANGUAGES
+        .iter()
+        .position(|l| Some(l.to_string()) == language)
+        .unwrap_or(0)
+}
+
+/// The `locale` of the wallet config, otherwise the first of the request's
+/// `ui_locales`
+pub fn locale() -> Option<String> {
+    CONFIG
+        .get()
+        .and_then(|a| a.locale.clone())
+        .or_else(|| REQUEST_LOCALE.get().cloned())
+}
+
+pub fn message(message: Message, locale: Option<&str>) -> &'static str {
+    message.translations()[language_index(locale)]
+}
+
+/// Resolves a display string of the database or request, which is either a
+/// plain string or an object mapping locales to strings.
+pub fn localized(value: &Value, locale: Option<&str>) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Object(o) => {
+            let display = o
+                .iter()
+                .filter_map(|(l, label)| {
+                    Some(ClaimDisplay {
+                        locale: Some(l.to_string()),
+                        label: label.as_str()?.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            select_label(&display, locale).map(|a| a.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{localized, message, Message};
+
+    #[test]
+    fn test_localized() {
+        let display = json!({ "en": "Card number", "de-CH": "Kartennummer" });
+        assert_eq!(
+            localized(&display, Some("de")).as_deref(),
+            Some("Kartennummer")
+        );
+        assert_eq!(
+            localized(&display, Some("fr")).as_deref(),
+            Some("Card number")
+        );
+        assert_eq!(
+            localized(&json!("Name"), Some("fr")).as_deref(),
+            Some("Name")
+        );
+        assert_eq!(message(Message::Yes, Some("it-CH")), "Sì");
+        assert_eq!(message(Message::Yes, None), "Yes");
+    }
+}
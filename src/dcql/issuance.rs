@@ -0,0 +1,146 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::models::{Credential, CredentialSetOption, DcqlQuery, Meta};
+
+/// A credential the wallet can obtain from an issuer, offered when no stored
+/// credential matches a request
+#[derive(Deserialize, Debug, Clone)]
+pub struct IssuanceOffer {
+    /// `vct` or doctype of the issued credential
+    pub document_type: String,
+    /// Returned to the wallet in the entry id, e.g. a credential offer URI
+    pub id: String,
+    /// Strings or objects mapping locales to strings
+    pub title: Value,
+    #[serde(default)]
+    pub subtitle: Value,
+    /// `start` and `length` of the icon in the credentials buffer
+    #[serde(default)]
+    pub icon: Value,
+}
+
+/// Whether no credential satisfies the query: a required credential set has no
+/// satisfiable option, or nothing matched at all
+pub fn is_unsatisfied(options: &[CredentialSetOption]) -> bool {
+    options.is_empty()
+        || options
+            .iter()
+            .any(|a| a.required && a.set_options.is_empty())
+}
+
+impl DcqlQuery {
+    /// Offers for the types of the credential queries none of the `credentials`
+    /// satisfies, in query order
+    pub fn issuance_offers<'a>(
+        &self,
+        offers: &'a [IssuanceOffer],
+        credentials: &[Credential],
+    ) -> Vec<&'a IssuanceOffer> {
+        let mut matching: Vec<&IssuanceOffer> = vec![];
+        let unsatisfied = self
+            .credentials
+            .iter()
+            .flatten()
+            .filter(|a| self.disclosures(credentials, a).is_empty());
+        for credential_query in unsatisfied {
+            let types = match &credential_query.meta {
+                Some(Meta::SdjwtVc { vct_values }) => vct_values.clone(),
+                Some(Meta::IsoMdoc { doctype_value }) => vec![doctype_value.clone()],
+                None => vec![],
+            };
+            for offer in offers.iter().filter(|a| types.contains(&a.document_type)) {
+                if !matching.iter().any(|a| a.id == offer.id) {
+                    matching.push(offer);
+                }
+            }
+        }
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{is_unsatisfied, IssuanceOffer};
+    use crate::dcql::models::{Credential, DcqlQuery};
+
+    #[test]
+    fn test_issuance_offers() {
+        let offers = serde_json::from_value::<Vec<IssuanceOffer>>(json!([
+            { "document_type": "org.iso.18013.5.1.mDL", "id": "mdl", "title": "Driving licence" },
+            { "document_type": "urn:eudi:pid:1", "id": "pid", "title": { "en": "PID" } }
+        ]))
+        .unwrap();
+        let query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [
+                {
+                    "id": "pid",
+                    "format": "dc+sd-jwt",
+                    "meta": { "vct_values": ["urn:eudi:pid:1"] }
+                },
+                {
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": { "doctype_value": "org.iso.18013.5.1.mDL" }
+                }
+            ]
+        }))
+        .unwrap();
+        let mdl = Credential::DummyCredential(json!({
+            "id": "1",
+            "document_type": "org.iso.18013.5.1.mDL",
+            "credential_format": "mso_mdoc"
+        }));
+        let matching = query.issuance_offers(&offers, std::slice::from_ref(&mdl));
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].id, "pid");
+
+        // the required set is returned without options if nothing satisfies it
+        let query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [
+                {
+                    "id": "pid",
+                    "format": "dc+sd-jwt",
+                    "meta": { "vct_values": ["urn:eudi:pid:1"] }
+                },
+                {
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": { "doctype_value": "org.iso.18013.5.1.mDL" }
+                }
+            ],
+            "credential_sets": [
+                { "options": [["pid"]] },
+                { "options": [["mdl"]], "required": false }
+            ]
+        }))
+        .unwrap();
+        let options = query.select_credentials(vec![mdl.clone()]);
+        assert!(options[0].set_options.is_empty());
+        assert!(is_unsatisfied(&options));
+        let matching = query.issuance_offers(&offers, &[mdl]);
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].id, "pid");
+    }
+}
@@ -0,0 +1,123 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use serde::Deserialize;
+
+use super::models::{Pointer, PointerPart};
+
+const MASK: &str = "••••";
+
+/// How a claim value is shown in the credential selector
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskingPolicy {
+    Reveal,
+    Last4,
+    Hide,
+}
+
+/// Masking policy of a database entry for the claims at `path`
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClaimMasking {
+    pub path: Pointer,
+    pub policy: MaskingPolicy,
+}
+
+impl ClaimMasking {
+    /// `null` in the path stands for any array element
+    pub fn applies_to(&self, path: &[PointerPart]) -> bool {
+        self.path.len() == path.len()
+            && self
+                .path
+                .iter()
+                .zip(path)
+                .all(|(a, b)| a == b || matches!(a, PointerPart::Null(_)))
+    }
+}
+
+/// Built-in policies for elements holding account numbers and secrets
+const BUILT_IN: [(&str, MaskingPolicy); 12] = [
+    ("card_number", MaskingPolicy::Last4),
+    ("pan", MaskingPolicy::Last4),
+    ("primary_account_number", MaskingPolicy::Last4),
+    ("account_number", MaskingPolicy::Last4),
+    ("iban", MaskingPolicy::Last4),
+    ("social_security_number", MaskingPolicy::Last4),
+    ("health_insurance_id", MaskingPolicy::Last4),
+    ("cvv", MaskingPolicy::Hide),
+    ("cvc", MaskingPolicy::Hide),
+    ("security_code", MaskingPolicy::Hide),
+    ("pin", MaskingPolicy::Hide),
+    ("password", MaskingPolicy::Hide),
+];
+
+pub fn built_in_policy(path: &[PointerPart]) -> MaskingPolicy {
+    let element = path.iter().rev().find_map(|p| match p {
+        PointerPart::String(s) => Some(s.to_lowercase()),
+        _ => None,
+    });
+    BUILT_IN
+        .iter()
+        .find(|(name, _)| element.as_deref() == Some(*name))
+        .map(|(_, policy)| *policy)
+        .unwrap_or(MaskingPolicy::Reveal)
+}
+
+/// Applies `policy` to a rendered value, e.g. `•••• 2312` for `Last4`
+pub fn mask(value: String, policy: MaskingPolicy) -> String {
+    match policy {
+        MaskingPolicy::Reveal => value,
+        MaskingPolicy::Hide => MASK.to_string(),
+        MaskingPolicy::Last4 => {
+            let chars = value
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<Vec<_>>();
+            // short values would be shown in full
+            if chars.len() <= 4 {
+                return MASK.to_string();
+            }
+            let last4 = chars[chars.len() - 4..].iter().collect::<String>();
+            format!("{MASK} {last4}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{built_in_policy, mask, MaskingPolicy};
+    use crate::dcql::models::PointerPart;
+
+    #[test]
+    fn test_mask() {
+        let card_number = [
+            PointerPart::from("com.emvco.payment_card.1"),
+            PointerPart::from("card_number"),
+        ];
+        let policy = built_in_policy(&card_number);
+        assert_eq!(policy, MaskingPolicy::Last4);
+        assert_eq!(mask(String::from("5466000055552312"), policy), "•••• 2312");
+        assert_eq!(mask(String::from("123"), policy), "••••");
+        assert_eq!(
+            built_in_policy(&[PointerPart::from("family_name")]),
+            MaskingPolicy::Reveal
+        );
+        assert_eq!(mask(String::from("123"), MaskingPolicy::Hide), "••••");
+    }
+}
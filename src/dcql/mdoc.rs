@@ -0,0 +1,327 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use base64::Engine;
+use ciborium::Value as CborValue;
+use serde_json::{Map, Number, Value};
+
+use super::{algorithm_name, claims_pointer::ClaimsTree, models::Credential, parsers::wrap_values};
+
+/// Tag of an embedded CBOR data item (`#6.24(bstr .cbor ...)`)
+const ENCODED_CBOR: u64 = 24;
+const COSE_HEADER_ALG: i128 = 1;
+
+/// The decoded parts of an mdoc `IssuerSigned` structure
+#[derive(Debug, Clone)]
+pub struct IssuerSigned {
+    /// `nameSpaces` as a map of namespace to element identifier to element value
+    pub name_spaces: CborValue,
+    pub doc_type: Option<String>,
+    pub issuer_alg: Option<String>,
+    /// `signed`, `validFrom` and `validUntil` of the MSO's `validityInfo`
+    pub signed: Option<String>,
+    pub valid_from: Option<String>,
+    pub valid_until: Option<String>,
+}
+
+impl ClaimsTree for CborValue {
+    fn get_key(&self, key: &str) -> Option<&Self> {
+        match self {
+            CborValue::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| k.as_text() == Some(key))
+                .map(|(_, v)| v),
+            CborValue::Tag(_, inner) => inner.get_key(key),
+            _ => None,
+        }
+    }
+    fn get_index(&self, index: usize) -> Option<&Self> {
+        match self {
+            CborValue::Array(elements) => elements.get(index),
+            CborValue::Tag(_, inner) => inner.get_index(index),
+            _ => None,
+        }
+    }
+    fn elements(&self) -> Option<Vec<&Self>> {
+        match self {
+            CborValue::Array(elements) => Some(elements.iter().collect()),
+            CborValue::Tag(_, inner) => inner.elements(),
+            _ => None,
+        }
+    }
+    fn is_object(&self) -> bool {
+        match self {
+            CborValue::Map(_) => true,
+            CborValue::Tag(_, inner) => inner.is_object(),
+            _ => false,
+        }
+    }
+    fn to_json(&self) -> Value {
+        match self {
+            CborValue::Integer(i) => {
+                let i = i128::from(*i);
+                if let Ok(i) = i64::try_from(i) {
+                    Value::from(i)
+                } else if let Ok(i) = u64::try_from(i) {
+                    Value::from(i)
+                } else {
+                    Value::String(i.to_string())
+                }
+            }
+            CborValue::Float(f) => Number::from_f64(*f).map(Value::Number).unwrap_or_default(),
+            CborValue::Text(s) => Value::String(s.to_string()),
+            CborValue::Bytes(b) => Value::Array(b.iter().map(|a| Value::from(*a)).collect()),
+            CborValue::Bool(b) => Value::Bool(*b),
+            CborValue::Tag(_, inner) => inner.to_json(),
+            CborValue::Array(a) => Value::Array(a.iter().map(|a| a.to_json()).collect()),
+            CborValue::Map(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = match k.as_text() {
+                            Some(k) => k.to_string(),
+                            None => k.to_json().to_string(),
+                        };
+                        (key, v.to_json())
+                    })
+                    .collect::<Map<_, _>>(),
+            ),
+            _ => Value::Null,
+        }
+    }
+}
+
+fn decode_cbor(bytes: &[u8]) -> Option<CborValue> {
+    ciborium::from_reader(bytes).ok()
+}
+
+fn decode_embedded(value: &CborValue) -> Option<CborValue> {
+    match value {
+        CborValue::Tag(ENCODED_CBOR, inner) => decode_cbor(inner.as_bytes()?),
+        _ => None,
+    }
+}
+
+/// Decodes the `IssuerSigned` CBOR structure of an mdoc, unwrapping all
+/// `IssuerSignedItem`s and the MSO.
+pub fn decode(bytes: &[u8]) -> Option<IssuerSigned> {
+    let issuer_signed = decode_cbor(bytes)?;
+    let mut name_spaces = vec![];
+    for (name_space, items) in issuer_signed.get_key("nameSpaces")?.as_map()? {
+        let mut elements = vec![];
+        for item in items.as_array()? {
+            let item = decode_embedded(item)?;
+            let identifier = item.get_key("elementIdentifier")?.clone();
+            let value = item.get_key("elementValue")?.clone();
+            elements.push((identifier, value));
+        }
+        name_spaces.push((name_space.clone(), CborValue::Map(elements)));
+    }
+    // issuerAuth is a COSE_Sign1 carrying the MSO as payload
+    let issuer_auth = issuer_signed
+        .get_key("issuerAuth")
+        .and_then(|a| a.as_array());
+    let issuer_alg = issuer_auth
+        .and_then(|a| a.first())
+        .and_then(|a| decode_cbor(a.as_bytes()?))
+        .and_then(|protected| {
+            let (_, alg) = protected
+                .as_map()?
+                .iter()
+                .find(|(k, _)| k.as_integer().map(i128::from) == Some(COSE_HEADER_ALG))?;
+            let alg = i64::try_from(i128::from(alg.as_integer()?)).ok()?;
+            algorithm_name(&Value::from(alg))
+        });
+    let mso = issuer_auth
+        .and_then(|a| a.get(2))
+        .and_then(|a| decode_cbor(a.as_bytes()?))
+        .and_then(|a| decode_embedded(&a));
+    let text = |value: &CborValue| match value {
+        CborValue::Tag(_, inner) => inner.as_text().map(|a| a.to_string()),
+        value => value.as_text().map(|a| a.to_string()),
+    };
+    let validity = |key: &str| {
+        mso.as_ref()
+            .and_then(|mso| mso.get_key("validityInfo")?.get_key(key))
+            .and_then(text)
+    };
+    Some(IssuerSigned {
+        name_spaces: CborValue::Map(name_spaces),
+        doc_type: mso.as_ref().and_then(|mso| text(mso.get_key("docType")?)),
+        issuer_alg,
+        signed: validity("signed"),
+        valid_from: validity("validFrom"),
+        valid_until: validity("validUntil"),
+    })
+}
+
+/// Decodes the base64url encoded `issuer_signed` of a database entry. The JSON
+/// representation of the claims is stored in `paths` for rendering, matching is
+/// done on the CBOR data.
+pub fn expand_entry(mut entry: Value, wrap: bool) -> Credential {
+    let decoded = entry["issuer_signed"]
+        .as_str()
+        .and_then(|a| {
+            base64::prelude::BASE64_URL_SAFE_NO_PAD
+                .decode(a.trim_end_matches('='))
+                .ok()
+        })
+        .and_then(|a| decode(&a));
+    let Some(issuer_signed) = decoded else {
+        return Credential::DummyCredential(entry);
+    };
+    if entry["document_type"].is_null() {
+        if let Some(doc_type) = issuer_signed.doc_type {
+            entry["document_type"] = Value::String(doc_type);
+        }
+    }
+    if entry["credential_format"].is_null() {
+        entry["credential_format"] = Value::String(String::from("mso_mdoc"));
+    }
+    if entry["issuer_alg"].is_null() {
+        if let Some(issuer_alg) = issuer_signed.issuer_alg {
+            entry["issuer_alg"] = Value::String(issuer_alg);
+        }
+    }
+    let validity = [
+        ("issued_at", issuer_signed.signed),
+        ("valid_from", issuer_signed.valid_from),
+        ("valid_until", issuer_signed.valid_until),
+    ];
+    for (key, value) in validity {
+        if let (true, Some(value)) = (entry[key].is_null(), value) {
+            entry[key] = Value::String(value);
+        }
+    }
+    let claims = issuer_signed.name_spaces.to_json();
+    entry["paths"] = if wrap { wrap_values(claims) } else { claims };
+    Credential::IssuerSigned(entry, issuer_signed.name_spaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use ciborium::Value as CborValue;
+    use serde_json::{json, Value};
+
+    use super::expand_entry;
+    use crate::dcql::{
+        claims_pointer::Selector,
+        models::{ClaimsQuery, Credential},
+    };
+
+    fn encode(value: &CborValue) -> Vec<u8> {
+        let mut bytes = vec![];
+        ciborium::into_writer(value, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn embedded(value: &CborValue) -> CborValue {
+        CborValue::Tag(24, Box::new(CborValue::Bytes(encode(value))))
+    }
+
+    fn item(id: i64, identifier: &str, value: CborValue) -> CborValue {
+        embedded(&CborValue::Map(vec![
+            (CborValue::from("digestID"), CborValue::from(id)),
+            (CborValue::from("random"), CborValue::Bytes(vec![1, 2, 3])),
+            (
+                CborValue::from("elementIdentifier"),
+                CborValue::from(identifier),
+            ),
+            (CborValue::from("elementValue"), value),
+        ]))
+    }
+
+    #[test]
+    fn test_issuer_signed() {
+        let tdate = |s: &str| CborValue::Tag(0, Box::new(CborValue::from(s)));
+        let mso = CborValue::Map(vec![
+            (
+                CborValue::from("docType"),
+                CborValue::from("org.iso.18013.5.1.mDL"),
+            ),
+            (
+                CborValue::from("validityInfo"),
+                CborValue::Map(vec![
+                    (CborValue::from("signed"), tdate("2025-01-01T00:00:00Z")),
+                    (CborValue::from("validFrom"), tdate("2025-01-01T00:00:00Z")),
+                    (CborValue::from("validUntil"), tdate("2030-01-01T00:00:00Z")),
+                ]),
+            ),
+        ]);
+        let protected = CborValue::Map(vec![(CborValue::from(1), CborValue::from(-7))]);
+        let issuer_signed = CborValue::Map(vec![
+            (
+                CborValue::from("nameSpaces"),
+                CborValue::Map(vec![(
+                    CborValue::from("org.iso.18013.5.1"),
+                    CborValue::Array(vec![
+                        item(0, "family_name", CborValue::from("Mustermann")),
+                        item(
+                            1,
+                            "birth_date",
+                            CborValue::Tag(1004, Box::new(CborValue::from("1964-08-12"))),
+                        ),
+                        item(2, "portrait", CborValue::Bytes(vec![0xff, 0xd8])),
+                    ]),
+                )]),
+            ),
+            (
+                CborValue::from("issuerAuth"),
+                CborValue::Array(vec![
+                    CborValue::Bytes(encode(&protected)),
+                    CborValue::Map(vec![]),
+                    CborValue::Bytes(encode(&embedded(&mso))),
+                    CborValue::Bytes(vec![0; 64]),
+                ]),
+            ),
+        ]);
+        let entry = json!({
+            "id": "1",
+            "issuer_signed": base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(encode(&issuer_signed))
+        });
+        let credential = expand_entry(entry, false);
+        let Credential::IssuerSigned(entry, name_spaces) = &credential else {
+            panic!("not decoded");
+        };
+        assert_eq!(entry["document_type"], "org.iso.18013.5.1.mDL");
+        assert_eq!(entry["issuer_alg"], "ES256");
+        assert_eq!(entry["valid_until"], "2030-01-01T00:00:00Z");
+        assert_eq!(
+            entry["paths"]["org.iso.18013.5.1"]["portrait"],
+            json!([255, 216])
+        );
+
+        let query = serde_json::from_value::<ClaimsQuery>(json!({
+            "path": ["org.iso.18013.5.1", "birth_date"],
+            "values": ["1964-08-12"]
+        }))
+        .unwrap();
+        assert!(query.matches(&credential));
+        let birth_date = query.path.select(name_spaces.clone()).unwrap();
+        assert!(matches!(birth_date[0], CborValue::Tag(1004, _)));
+
+        let query = serde_json::from_value::<ClaimsQuery>(json!({
+            "path": ["org.iso.18013.5.1", "family_name"],
+            "values": [Value::from("Musterfrau")]
+        }))
+        .unwrap();
+        assert!(!query.matches(&credential));
+    }
+}
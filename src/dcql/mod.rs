@@ -18,34 +18,71 @@ specific language governing permissions and limitations
 under the License.
  */
 pub mod claims_pointer;
+#[cfg(feature = "entrysets")]
+pub mod combinations;
+pub mod i18n;
+#[cfg(feature = "inlineissuance")]
+pub mod issuance;
+pub mod masking;
+pub mod mdoc;
 pub mod models;
 pub mod parsers;
+pub mod preview;
+pub mod ranking;
+pub mod registry;
+pub mod render;
+pub mod risk;
+pub mod scoring;
+pub mod sdjwt;
+pub mod template;
+pub mod transaction_data;
+pub mod type_metadata;
 
-use claims_pointer::Selector;
+use crate::credman::haip;
+use claims_pointer::{ClaimsTree, Selector};
+use i18n::localized;
+use masking::{built_in_policy, ClaimMasking, MaskingPolicy};
 use models::{
-    ClaimsQuery, Credential, CredentialOptions, CredentialQuery, CredentialSetOption, DcqlQuery,
-    Disclosure, Pointer, PointerPart, SetOption,
+    ClaimsQuery, ClientMetadata, Credential, CredentialOptions, CredentialQuery,
+    CredentialSetOption, DcqlQuery, Disclosure, Pointer, PointerPart, SetOption, VpFormat,
+    WalletConfig,
 };
-use parsers::PARSER;
+use parsers::{CONFIG, PARSER};
 use serde_json::Value;
 use std::collections::BTreeMap;
+use type_metadata::{claim_label, select_label, vct_chain, ClaimMetadata, StoredTypeMetadata};
+
+/// SD-JWT VC claims are looked up by type, mdoc claims by their namespace
+fn registry_label(path: &[PointerPart], chain: &[String], locale: Option<&str>) -> Option<String> {
+    let name = |parts: &[PointerPart]| {
+        parts
+            .iter()
+            .filter_map(|p| match p {
+                PointerPart::String(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let label = chain
+        .iter()
+        .find_map(|vct| registry::claim_label(vct, &name(path), locale))
+        .or_else(|| match path {
+            [PointerPart::String(namespace), rest @ ..] if !rest.is_empty() => {
+                registry::claim_label(namespace, &name(rest), locale)
+            }
+            _ => None,
+        });
+    label.map(|a| a.to_string())
+}
 
 pub trait InformationScore {
     fn score(&self) -> usize;
 }
 
-const DANGEROUS_PROPERTIES: [&str; 4] = ["birth", "date", "address", "street"];
-const HIDING_PROPERTIES: [&str; 1] = ["age_over"];
-
 impl InformationScore for &str {
     fn score(&self) -> usize {
-        if DANGEROUS_PROPERTIES.iter().any(|a| self.contains(a)) {
-            return 4;
-        }
-        if HIDING_PROPERTIES.iter().any(|a| self.contains(a)) {
-            return 1;
-        }
-        2
+        scoring::policy().weight(&[PointerPart::from(*self)])
     }
 }
 impl InformationScore for String {
@@ -55,49 +92,12 @@ impl InformationScore for String {
 }
 impl InformationScore for Vec<String> {
     fn score(&self) -> usize {
-        let mut score = 0;
-        for attribute in self {
-            for dp in &DANGEROUS_PROPERTIES {
-                if attribute.contains(dp) {
-                    score += 4;
-                    continue;
-                }
-            }
-            for hiding_property in &HIDING_PROPERTIES {
-                if attribute.contains(hiding_property) {
-                    score += 1;
-                    continue;
-                }
-            }
-            score += 2;
-        }
-        score
+        self.iter().map(|a| a.score()).sum()
     }
 }
 impl InformationScore for Pointer {
     fn score(&self) -> usize {
-        let mut score = 0;
-        for p in self {
-            match p {
-                PointerPart::String(attribute) => {
-                    for dp in &DANGEROUS_PROPERTIES {
-                        if attribute.contains(dp) {
-                            score += 4;
-                            continue;
-                        }
-                    }
-                    for hiding_property in &HIDING_PROPERTIES {
-                        if attribute.contains(hiding_property) {
-                            score += 1;
-                            continue;
-                        }
-                    }
-                    score += 2;
-                }
-                _ => continue,
-            }
-        }
-        score
+        scoring::policy().weight(self)
     }
 }
 
@@ -124,7 +124,7 @@ impl DcqlQuery {
                 .map(|a| (a.id.clone(), a))
                 .collect::<BTreeMap<_, _>>();
             let mut matching_sets: Vec<CredentialSetOption> = vec![];
-            for credential_set in credential_sets {
+            for (index, credential_set) in credential_sets.iter().enumerate() {
                 let mut variations = vec![];
                 'option_loop: for option in &credential_set.options {
                     let mut possible_candidates: BTreeMap<String, CredentialOptions> =
@@ -133,15 +133,7 @@ impl DcqlQuery {
                         let Some(credential_query) = credential_query_map.get(id) else {
                             continue 'option_loop;
                         };
-                        let creds = credentials
-                            .iter()
-                            .filter_map(|a| {
-                                a.is_satisfied(credential_query).map(|claims| Disclosure {
-                                    credential: a.clone(),
-                                    claims_queries: claims,
-                                })
-                            })
-                            .collect::<Vec<_>>();
+                        let creds = self.disclosures(&credentials, credential_query);
 
                         possible_candidates.insert(
                             credential_query.id.clone(),
@@ -158,31 +150,20 @@ impl DcqlQuery {
                 }
                 if !variations.is_empty() {
                     matching_sets.push(CredentialSetOption {
-                        purpose: credential_set
-                            .purpose
-                            .as_ref()
-                            .and_then(|a| a.as_str().map(|a| a.to_string())),
+                        purpose: credential_set.purpose.clone(),
+                        required: credential_set.required,
+                        index,
+                        // an option is only satisfied if all of its credential queries are
                         set_options: variations
                             .into_iter()
-                            .filter_map(|bt| {
-                                let r = bt
-                                    .into_iter()
-                                    .filter_map(|kv| {
-                                        if kv.1.options.is_empty() {
-                                            None
-                                        } else {
-                                            Some(SetOption {
-                                                id: kv.0,
-                                                options: kv.1.options,
-                                            })
-                                        }
+                            .filter(|bt| bt.values().all(|a| !a.options.is_empty()))
+                            .map(|bt| {
+                                bt.into_iter()
+                                    .map(|(id, a)| SetOption {
+                                        id,
+                                        options: a.options,
                                     })
-                                    .collect::<Vec<_>>();
-                                if r.is_empty() {
-                                    None
-                                } else {
-                                    Some(r)
-                                }
+                                    .collect()
                             })
                             .collect(),
                     })
@@ -194,15 +175,7 @@ impl DcqlQuery {
             let mut matching_sets: Vec<CredentialSetOption> = vec![];
             let mut map = vec![];
             for credential_query in credential_queries {
-                let creds: Vec<_> = credentials
-                    .iter()
-                    .filter_map(|a| {
-                        a.is_satisfied(credential_query).map(|claims| Disclosure {
-                            credential: a.clone(),
-                            claims_queries: claims,
-                        })
-                    })
-                    .collect();
+                let creds = self.disclosures(&credentials, credential_query);
                 if creds.is_empty() {
                     return vec![];
                 }
@@ -214,12 +187,102 @@ impl DcqlQuery {
             }
             matching_sets.push(CredentialSetOption {
                 purpose: None,
+                required: true,
+                index: 0,
                 set_options: vec![map],
             });
             return matching_sets;
         }
         vec![]
     }
+
+    /// All credentials satisfying `credential_query`, including the transaction data
+    /// referencing it.
+    fn disclosures(
+        &self,
+        credentials: &[Credential],
+        credential_query: &CredentialQuery,
+    ) -> Vec<Disclosure> {
+        let transaction_data_types = self
+            .transaction_data
+            .iter()
+            .filter(|a| a.credential_ids.contains(&credential_query.id))
+            .map(|a| a.r#type.as_str())
+            .collect::<Vec<_>>();
+        credentials
+            .iter()
+            .filter(|a| a.supports_transaction_data(&transaction_data_types))
+            .filter_map(|a| a.is_satisfied(credential_query, &self.vp_formats_supported))
+            .flatten()
+            .collect()
+    }
+}
+
+/// Algorithm identifiers are compared by their JOSE name, COSE identifiers are mapped.
+/// The fully specified ECDSA names denote the same curves as the JOSE ones.
+pub fn algorithm_name(alg: &Value) -> Option<String> {
+    let name = match alg {
+        Value::String(s) => s.as_str(),
+        Value::Number(n) => match n.as_i64()? {
+            -7 | -9 => "ES256",
+            -35 | -51 => "ES384",
+            -36 | -52 => "ES512",
+            -8 => "EdDSA",
+            other => return Some(other.to_string()),
+        },
+        _ => return None,
+    };
+    let name = match name {
+        "ESP256" => "ES256",
+        "ESP384" => "ES384",
+        "ESP512" => "ES512",
+        name => name,
+    };
+    Some(name.to_string())
+}
+
+/// An algorithm is accepted, if either side does not state any restrictions.
+fn accepts_algorithm(values: &Option<Vec<Value>>, alg: &Option<String>) -> bool {
+    let (Some(values), Some(alg)) = (values, alg) else {
+        return true;
+    };
+    values.iter().filter_map(algorithm_name).any(|a| &a == alg)
+}
+
+impl WalletConfig {
+    /// Whether the wallet is able to encrypt a response to the verifier.
+    pub fn supports_response_encryption(&self, client_metadata: &ClientMetadata) -> bool {
+        let enc_values = client_metadata
+            .encrypted_response_enc_values_supported
+            .clone()
+            .unwrap_or(vec![String::from("A128GCM")]);
+        if !self.encryption_enc_values.is_empty()
+            && !enc_values
+                .iter()
+                .any(|e| self.encryption_enc_values.contains(e))
+        {
+            return false;
+        }
+        let key_algs = client_metadata
+            .jwks
+            .as_ref()
+            .and_then(|a| a["keys"].as_array())
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k["alg"].as_str().map(|a| a.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if !self.encryption_alg_values.is_empty()
+            && !key_algs.is_empty()
+            && !key_algs
+                .iter()
+                .any(|a| self.encryption_alg_values.contains(a))
+        {
+            return false;
+        }
+        true
+    }
 }
 
 pub struct DisplayMetadata {
@@ -230,47 +293,161 @@ pub struct DisplayMetadata {
 }
 
 impl Credential {
-    pub fn get_display_metadata(&self) -> DisplayMetadata {
-        match self {
-            Credential::DummyCredential(value) => {
-                let id = value["id"]
-                    .as_str()
-                    .map(|a| a.to_string())
-                    .unwrap_or_default();
-                let title = value["title"]
-                    .as_str()
-                    .map(|a| a.to_string())
-                    .unwrap_or_default();
-                let subtitle = value["subtitle"]
-                    .as_str()
-                    .map(|a| a.to_string())
-                    .unwrap_or_default();
-                let icon = value["icon"].clone();
+    /// `title` and `subtitle` are either strings or objects mapping locales to strings.
+    /// They are only used if there is no `title_template`/`subtitle_template` or
+    /// its claims are missing.
+    pub fn get_display_metadata(&self, locale: Option<&str>) -> DisplayMetadata {
+        let value = self.entry();
+        let id = value["id"]
+            .as_str()
+            .map(|a| a.to_string())
+            .unwrap_or_default();
+        let display = |key: &str| {
+            localized(&value[format!("{key}_template")], locale)
+                .and_then(|t| template::evaluate(&t, self, locale))
+                .or_else(|| localized(&value[key], locale))
+                .unwrap_or_default()
+        };
+        let title = display("title");
+        let subtitle = display("subtitle");
+        let icon = value["icon"].clone();
 
-                DisplayMetadata {
-                    id,
-                    title,
-                    subtitle,
-                    icon,
-                }
-            }
+        DisplayMetadata {
+            id,
+            title,
+            subtitle,
+            icon,
         }
     }
-    fn get_credential_format(&self) -> Option<String> {
+    /// The database entry of the credential
+    pub fn entry(&self) -> &Value {
         match self {
-            Credential::DummyCredential(value) => {
-                value["credential_format"].as_str().map(|a| a.to_string())
-            }
+            Credential::DummyCredential(value)
+            | Credential::IssuerSigned(value, _)
+            | Credential::SdJwt(value, _) => value,
         }
     }
+    fn get_credential_format(&self) -> Option<String> {
+        self.entry()["credential_format"]
+            .as_str()
+            .map(|a| a.to_string())
+    }
     fn get_document_type(&self) -> Option<String> {
-        match self {
-            Credential::DummyCredential(value) => {
-                value["document_type"].as_str().map(|a| a.to_string())
+        self.entry()["document_type"]
+            .as_str()
+            .map(|a| a.to_string())
+    }
+    fn get_transaction_data_types(&self) -> Vec<String> {
+        self.entry()["transaction_data_types"]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|t| t.as_str().map(|t| t.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    /// A credential can only be presented if it handles every transaction data type
+    /// referencing its credential query.
+    pub fn supports_transaction_data(&self, transaction_data_types: &[&str]) -> bool {
+        if transaction_data_types.is_empty() {
+            return true;
+        }
+        let supported = self.get_transaction_data_types();
+        transaction_data_types
+            .iter()
+            .all(|t| supported.iter().any(|s| s == t))
+    }
+    pub fn get_type_metadata(&self) -> Vec<StoredTypeMetadata> {
+        self.entry()["type_metadata"]
+            .as_array()
+            .map(|a| a.iter().filter_map(StoredTypeMetadata::parse).collect())
+            .unwrap_or_default()
+    }
+    /// The entry's `masking` policy for the claim at `path`, otherwise the built-in one
+    pub fn get_masking_policy(&self, path: &[PointerPart]) -> MaskingPolicy {
+        let rules = serde_json::from_value::<Vec<ClaimMasking>>(self.entry()["masking"].clone())
+            .unwrap_or_default();
+        rules
+            .into_iter()
+            .find(|rule| rule.applies_to(path))
+            .map(|rule| rule.policy)
+            .unwrap_or_else(|| built_in_policy(path))
+    }
+    /// Labels of the entry's `claim_display`, in the format of type metadata claims
+    fn get_claim_display(&self) -> Vec<ClaimMetadata> {
+        serde_json::from_value(self.entry()["claim_display"].clone()).unwrap_or_default()
+    }
+    /// Display label of a claim from the entry's `claim_display` or the
+    /// credential's type metadata, falling back to the built-in registry of
+    /// well-known types and namespaces.
+    pub fn get_claim_label(&self, path: &[PointerPart], locale: Option<&str>) -> Option<String> {
+        let own = self
+            .get_claim_display()
+            .into_iter()
+            .filter(|claim| claim.applies_to(path))
+            .find_map(|claim| select_label(&claim.display, locale).map(|a| a.to_string()));
+        if own.is_some() {
+            return own;
+        }
+        let chain = self
+            .get_document_type()
+            .map(|a| self.get_vct_chain(&a))
+            .unwrap_or_default();
+        claim_label(path, &chain, &self.get_type_metadata(), locale)
+            .or_else(|| registry_label(path, &chain, locale))
+    }
+    fn get_vct_chain(&self, vct: &str) -> Vec<String> {
+        let claims = self.get_claims();
+        let integrity = self.entry()["vct#integrity"]
+            .as_str()
+            .or(claims["vct#integrity"].as_str())
+            .or(claims["vct#integrity"]["value"].as_str())
+            .map(|a| a.to_string());
+        vct_chain(vct, integrity.as_deref(), &self.get_type_metadata())
+    }
+    fn get_algorithm(&self, key: &str) -> Option<String> {
+        algorithm_name(&self.entry()[key])
+    }
+    /// Checks the credential's issuer and holder key algorithms against the
+    /// algorithms the verifier accepts for its format.
+    pub fn is_verifiable(&self, vp_formats_supported: &BTreeMap<String, VpFormat>) -> bool {
+        let issuer_alg = self.get_algorithm("issuer_alg");
+        let holder_alg = self.get_algorithm("holder_alg");
+        if haip::is_enforced()
+            && !haip::accepts_credential(issuer_alg.as_deref(), holder_alg.as_deref())
+        {
+            return false;
+        }
+        if vp_formats_supported.is_empty() {
+            return true;
+        }
+        let Some(format) = self.get_credential_format() else {
+            return true;
+        };
+        let Some(vp_format) = vp_formats_supported.get(&format) else {
+            return false;
+        };
+        match format.as_str() {
+            "mso_mdoc" => {
+                accepts_algorithm(&vp_format.issuerauth_alg_values, &issuer_alg)
+                    && accepts_algorithm(&vp_format.deviceauth_alg_values, &holder_alg)
+                    && accepts_algorithm(&vp_format.alg, &issuer_alg)
+                    && accepts_algorithm(&vp_format.alg, &holder_alg)
+            }
+            _ => {
+                accepts_algorithm(&vp_format.sd_jwt_alg_values, &issuer_alg)
+                    && accepts_algorithm(&vp_format.kb_jwt_alg_values, &holder_alg)
             }
         }
     }
-    pub fn is_satisfied(&self, credential_query: &CredentialQuery) -> Option<Vec<ClaimsQuery>> {
+    /// The ways the credential satisfies `credential_query`: one disclosure per
+    /// satisfiable claim set, in the order of the claim set policy.
+    pub fn is_satisfied(
+        &self,
+        credential_query: &CredentialQuery,
+        vp_formats_supported: &BTreeMap<String, VpFormat>,
+    ) -> Option<Vec<Disclosure>> {
         let format = credential_query.format.clone();
         // check that the requested format matches
         if let Some(f) = self.get_credential_format() {
@@ -278,13 +455,22 @@ impl Credential {
                 return None;
             }
         }
+        // skip credentials the verifier cannot verify
+        if !self.is_verifiable(vp_formats_supported) {
+            return None;
+        }
         let Some(document_type) = self.get_document_type() else {
             return None;
         };
         // test for document_type
         match &credential_query.meta {
             Some(models::Meta::SdjwtVc { vct_values }) => {
-                if !vct_values.contains(&document_type) {
+                // types extending a requested type are accepted as well
+                if !self
+                    .get_vct_chain(&document_type)
+                    .iter()
+                    .any(|vct| vct_values.contains(vct))
+                {
                     return None;
                 }
             }
@@ -299,7 +485,6 @@ impl Credential {
         if let (Some(claims_sets), Some(claims)) =
             (&credential_query.claim_sets, &credential_query.claims)
         {
-            let mut order_least = claims_sets.clone();
             // if claims_set is set all claims need an id
             // https://openid.net/specs/openid-4-verifiable-presentations-1_0-23.html#section-6.1
             if !claims.iter().all(|a| a.id().is_some()) {
@@ -309,21 +494,12 @@ impl Credential {
                 .iter()
                 .map(|a| (a.id().unwrap_or(String::from("<invalid>")), a.to_owned()))
                 .collect::<BTreeMap<_, _>>();
-            // we SHOULD use the "principle of least information".
-            order_least.sort_by(|a, b| {
-                let left: usize = a
-                    .iter()
-                    .filter_map(|e| claims_map.get(e))
-                    .map(|a| a.path.score())
-                    .sum();
-                let right = b
-                    .iter()
-                    .filter_map(|e| claims_map.get(e))
-                    .map(|a| a.path.score())
-                    .sum();
-                left.cmp(&right)
-            });
-            //find first matching claims set
+            // we SHOULD use the "principle of least information", unless the
+            // wallet is configured to follow the verifier's preference.
+            let claim_set_policy = CONFIG.get().map(|a| a.claim_set_policy).unwrap_or_default();
+            let order_least = claim_set_policy.order(scoring::policy(), claims_sets, &claims_map);
+            // every matching claims set is offered, so the user can choose
+            let mut disclosures = vec![];
             'claim_set: for claim_set in order_least {
                 let mut queries = vec![];
                 for claim_query_id in &claim_set {
@@ -335,7 +511,15 @@ impl Credential {
                     }
                     queries.push(claim_query.clone());
                 }
-                return Some(queries);
+                disclosures.push(Disclosure {
+                    credential: self.clone(),
+                    claims_queries: queries,
+                    claim_set: claims_sets.iter().position(|a| a == &claim_set),
+                    preference: disclosures.len(),
+                });
+            }
+            if !disclosures.is_empty() {
+                return Some(disclosures);
             }
         }
         //when we have no claims_sets we need to check all claim_querries
@@ -345,47 +529,63 @@ impl Credential {
                     return None;
                 }
             }
-            Some(vec![])
-        } else {
-            Some(vec![])
         }
+        Some(vec![Disclosure {
+            credential: self.clone(),
+            claims_queries: vec![],
+            claim_set: None,
+            preference: 0,
+        }])
     }
     pub fn get_claims(&self) -> serde_json::Value {
-        match self {
-            Credential::DummyCredential(value) => value["paths"].clone(),
-        }
+        self.entry()["paths"].clone()
     }
 }
 
 impl ClaimsQuery {
     pub fn matches(&self, credential: &Credential) -> bool {
-        match (self, credential.get_claims()) {
+        match (self, credential) {
+            // decoded mdocs are selected from their CBOR data, which does not
+            // follow the parser specific format
+            (ClaimsQuery { path, values, .. }, Credential::IssuerSigned(_, name_spaces)) => {
+                matches_values(path, name_spaces.clone(), values)
+            }
+            // as are decoded SD-JWT VCs, whose claims are stored wrapped
+            (ClaimsQuery { path, values, .. }, Credential::SdJwt(_, claims)) => {
+                matches_values(path, claims.clone(), values)
+            }
             (
                 ClaimsQuery {
                     id: _id,
                     path,
                     values,
+                    intent_to_retain: _,
                 },
-                data,
+                credential,
             ) => {
                 let path = if let Some(parser) = PARSER.get() {
-                    parser.path_transform(&path)
+                    parser.path_transform(path)
                 } else {
                     path.to_vec()
                 };
-
-                let Ok(data) = path.select(data) else {
-                    return false;
-                };
-                if let Some(vals) = values.as_ref() {
-                    return data.iter().all(|dr| vals.iter().any(|v| v == dr));
-                }
-                true
+                matches_values(&path, credential.get_claims(), values)
             }
         }
     }
 }
 
+fn matches_values<V: ClaimsTree>(path: &Pointer, data: V, values: &Option<Vec<Value>>) -> bool {
+    let Ok(data) = path.select(data) else {
+        return false;
+    };
+    if let Some(vals) = values.as_ref() {
+        return data
+            .iter()
+            .all(|dr| vals.iter().any(|v| v == &dr.to_json()));
+    }
+    true
+}
+
 // pub fn select_credentials(query: DcqlQuery, credentials: Vec<String>) -> Vec<CredentialSetOption> {
 //     query
 //         .select_credentials(credentials.iter().map(String::as_str).collect::<Vec<_>>())
@@ -395,9 +595,21 @@ impl ClaimsQuery {
 
 #[cfg(test)]
 mod tests {
-    use crate::dcql::{models::DcqlQuery, parsers::PARSER};
+    use base64::Engine;
+    use serde_json::{json, Value};
+
+    use crate::dcql::{
+        models::{
+            ClientMetadata, Credential, DcqlQuery, OptionalSelection, PointerPart, Selection,
+            TransactionData, WalletConfig,
+        },
+        parsers::PARSER,
+    };
 
-    use super::parsers::{CMWalletDatabaseFormat, ParseCredential, UbiqueWalletDatabaseFormat};
+    use super::{
+        algorithm_name,
+        parsers::{CMWalletDatabaseFormat, ParseCredential, Parser, UbiqueWalletDatabaseFormat},
+    };
 
     #[test]
     fn test_dcql() {
@@ -421,4 +633,213 @@ mod tests {
         let first_set = first_option.set_options.first().unwrap();
         assert!(!first_set.is_empty());
     }
+    #[test]
+    fn test_cm_format_sd_jwt_arrays() {
+        let encode =
+            |value: Value| base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(value.to_string());
+        let sd_jwt = format!(
+            "{}.{}.signature~",
+            encode(json!({ "alg": "ES256", "typ": "dc+sd-jwt" })),
+            encode(json!({ "vct": "pid", "nationalities": ["DE", "FR"] }))
+        );
+        let database = json!({
+            "credentials": {
+                "mso_mdoc": {},
+                "dc+sd-jwt": { "pid": [{ "id": "1", "sd_jwt": sd_jwt }] }
+            }
+        });
+        let creds = CMWalletDatabaseFormat.parse(&database.to_string()).unwrap();
+        let query = |path: Value, values: Value| {
+            serde_json::from_value::<DcqlQuery>(json!({
+                "credentials": [{
+                    "id": "pid",
+                    "format": "dc+sd-jwt",
+                    "meta": { "vct_values": ["pid"] },
+                    "claims": [{ "path": path, "values": values }]
+                }]
+            }))
+            .unwrap()
+        };
+        let matches = |query: DcqlQuery| !query.select_credentials(creds.clone()).is_empty();
+        assert!(matches(query(json!(["nationalities"]), Value::Null)));
+        assert!(matches(query(
+            json!(["nationalities", null]),
+            json!(["DE", "FR"])
+        )));
+        assert!(matches(query(json!(["nationalities", 0]), json!(["DE"]))));
+        assert!(!matches(query(json!(["nationalities", 1]), json!(["DE"]))));
+        // elements are shown one by one
+        let fields = CMWalletDatabaseFormat.get_fields(
+            &[PointerPart::from("nationalities"), PointerPart::Null(None)],
+            String::from("nationalities"),
+            &creds[0],
+        );
+        assert_eq!(fields[1].1.as_deref(), Some("FR"));
+    }
+    #[test]
+    fn test_claim_sets() {
+        let query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [{
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "claims": [
+                    { "id": "birth_date", "path": ["birth_date"] },
+                    { "id": "age", "path": ["age_over_18"] },
+                    { "id": "photo", "path": ["portrait"] }
+                ],
+                "claim_sets": [["birth_date"], ["age"], ["photo"]]
+            }]
+        }))
+        .unwrap();
+        let credential = Credential::DummyCredential(json!({
+            "id": "1",
+            "document_type": "pid",
+            "paths": {
+                "birth_date": { "value": "1964-08-12" },
+                "age_over_18": { "value": true }
+            }
+        }));
+        let r = query.select_credentials(vec![credential]);
+        let claim_sets = r[0].set_options[0][0]
+            .options
+            .iter()
+            .map(|a| a.claim_set)
+            .collect::<Vec<_>>();
+        assert_eq!(claim_sets, [Some(1), Some(0)]);
+    }
+    #[test]
+    fn test_optional_credential_sets() {
+        let query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [
+                { "id": "pid", "format": "dc+sd-jwt", "meta": { "vct_values": ["pid"] } },
+                { "id": "loyalty", "format": "dc+sd-jwt", "meta": { "vct_values": ["loyalty"] } }
+            ],
+            "credential_sets": [
+                { "options": [["loyalty"]], "required": false },
+                { "options": [["pid"]] }
+            ]
+        }))
+        .unwrap();
+        let credentials = ["pid", "loyalty"]
+            .map(|a| Credential::DummyCredential(json!({ "id": a, "document_type": a })));
+        let r = query.select_credentials(credentials.to_vec());
+        let sets = r.iter().map(|a| (a.index, a.required)).collect::<Vec<_>>();
+        assert_eq!(sets, [(0, false), (1, true)]);
+
+        let selection = Selection {
+            provider_index: 0,
+            claim_set: None,
+            optional: vec![OptionalSelection {
+                credential_set: 0,
+                credential_query: String::from("loyalty"),
+                credential_id: String::from("loyalty"),
+                claim_set: None,
+            }],
+        };
+        assert_eq!(
+            selection.to_json("pid"),
+            json!({
+                "provider_idx": 0,
+                "id": "pid",
+                "optional": [{ "credential_set": 0, "credential_query": "loyalty", "id": "loyalty" }]
+            })
+        );
+    }
+    #[test]
+    fn test_transaction_data() {
+        let creds = include_str!("./test_vectors/cm_format_db.json");
+        let creds = CMWalletDatabaseFormat.parse(creds).unwrap();
+        let mut query = serde_json::from_str::<DcqlQuery>(
+            r#"{
+                "credentials": [
+                    {
+                        "id": "card",
+                        "format": "mso_mdoc",
+                        "meta": { "doctype_value": "com.emvco.payment_card" }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let r = query.select_credentials(creds.clone());
+        assert_eq!(r[0].set_options[0][0].options.len(), 2);
+
+        // {"type":"payment_data","credential_ids":["card"],"amount":"42.00"}
+        let transaction_data =
+            "eyJ0eXBlIjoicGF5bWVudF9kYXRhIiwiY3JlZGVudGlhbF9pZHMiOlsiY2FyZCJdLCJhbW91bnQiOiI0Mi4wMCJ9"
+                .parse::<TransactionData>()
+                .unwrap();
+        assert_eq!(transaction_data.data["amount"], "42.00");
+        query.transaction_data.push(transaction_data.clone());
+        let r = query.select_credentials(creds.clone());
+        let options = &r[0].set_options[0][0].options;
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].credential.get_display_metadata(None).id, "2");
+
+        query.transaction_data[0].r#type = String::from("qes_authorization");
+        assert!(query.select_credentials(creds).is_empty());
+    }
+    #[test]
+    fn test_vp_formats_supported() {
+        let creds = include_str!("./test_vectors/cm_format_db.json");
+        let creds = CMWalletDatabaseFormat.parse(creds).unwrap();
+        let mut query = serde_json::from_str::<DcqlQuery>(
+            r#"{
+                "credentials": [
+                    {
+                        "id": "pid",
+                        "format": "dc+sd-jwt",
+                        "meta": { "vct_values": ["urn:eu.europa.ec.eudi:pid:1"] }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let client_metadata = serde_json::from_str::<ClientMetadata>(
+            r#"{
+                "vp_formats_supported": {
+                    "dc+sd-jwt": {
+                        "sd-jwt_alg_values": ["ES256"],
+                        "kb-jwt_alg_values": ["ES256"]
+                    },
+                    "mso_mdoc": { "issuerauth_alg_values": [-7] }
+                }
+            }"#,
+        )
+        .unwrap();
+        query.vp_formats_supported = client_metadata.vp_formats_supported.clone();
+        assert_eq!(query.select_credentials(creds.clone()).len(), 1);
+        assert_eq!(algorithm_name(&Value::from(-9)).as_deref(), Some("ES256"));
+        assert_eq!(
+            algorithm_name(&Value::from("ESP256")).as_deref(),
+            Some("ES256")
+        );
+
+        query
+            .vp_formats_supported
+            .get_mut("dc+sd-jwt")
+            .unwrap()
+            .sd_jwt_alg_values = Some(vec![Value::from("ES384")]);
+        assert!(query.select_credentials(creds.clone()).is_empty());
+
+        query.vp_formats_supported.remove("dc+sd-jwt");
+        assert!(query.select_credentials(creds).is_empty());
+    }
+    #[test]
+    fn test_response_encryption() {
+        let client_metadata = serde_json::from_str::<ClientMetadata>(
+            r#"{
+                "encrypted_response_enc_values_supported": ["A256GCM"],
+                "jwks": { "keys": [{ "kty": "EC", "alg": "ECDH-ES" }] }
+            }"#,
+        )
+        .unwrap();
+        let mut config = WalletConfig::default();
+        assert!(config.supports_response_encryption(&client_metadata));
+        config.encryption_alg_values = vec![String::from("ECDH-ES")];
+        config.encryption_enc_values = vec![String::from("A128GCM")];
+        assert!(!config.supports_response_encryption(&client_metadata));
+        config.encryption_enc_values.push(String::from("A256GCM"));
+        assert!(config.supports_response_encryption(&client_metadata));
+    }
 }
@@ -17,15 +17,88 @@ KIND, either express or implied.  See the License for the
 specific language governing permissions and limitations
 under the License.
  */
+use base64::Engine;
 use serde::Deserialize;
-use serde_json::{Number, Value};
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeMap;
 use std::hash::Hash;
 use std::str::FromStr;
 
+use super::scoring::{ClaimSetPolicy, ScoringPolicy};
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct DcqlQuery {
     pub credentials: Option<Vec<CredentialQuery>>,
     pub credential_sets: Option<Vec<CredentialSetQuery>>,
+    /// Decoded `transaction_data` of the surrounding OpenID4VP request
+    #[serde(skip)]
+    pub transaction_data: Vec<TransactionData>,
+    /// `vp_formats_supported` from the verifier's `client_metadata`
+    #[serde(skip)]
+    pub vp_formats_supported: BTreeMap<String, VpFormat>,
+    /// Name of the verifier as shown to the user
+    #[serde(skip)]
+    pub verifier: Option<String>,
+    #[serde(skip)]
+    pub client_id: Option<String>,
+    /// Whether the request was a signed request object
+    #[serde(skip)]
+    pub signed: bool,
+}
+
+/// The parts of the verifier's `client_metadata` the matcher negotiates against
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ClientMetadata {
+    pub client_name: Option<String>,
+    #[serde(default, alias = "vp_formats")]
+    pub vp_formats_supported: BTreeMap<String, VpFormat>,
+    pub encrypted_response_enc_values_supported: Option<Vec<String>>,
+    pub jwks: Option<Value>,
+}
+
+/// Algorithms a verifier accepts for one credential format. JOSE algorithms are
+/// strings, COSE algorithms for `mso_mdoc` are integers.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct VpFormat {
+    #[serde(rename = "sd-jwt_alg_values")]
+    pub sd_jwt_alg_values: Option<Vec<Value>>,
+    #[serde(rename = "kb-jwt_alg_values")]
+    pub kb_jwt_alg_values: Option<Vec<Value>>,
+    pub issuerauth_alg_values: Option<Vec<Value>>,
+    pub deviceauth_alg_values: Option<Vec<Value>>,
+    pub alg: Option<Vec<Value>>,
+}
+
+/// Wallet wide settings stored alongside the credentials in the database
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct WalletConfig {
+    /// JWE `alg` values the wallet can encrypt responses with
+    #[serde(default)]
+    pub encryption_alg_values: Vec<String>,
+    /// JWE `enc` values the wallet can encrypt responses with
+    #[serde(default)]
+    pub encryption_enc_values: Vec<String>,
+    /// Enforce the OpenID4VC High Assurance Interoperability Profile
+    #[serde(default)]
+    pub haip: bool,
+    /// Preferred locale for labels, e.g. `de-CH`
+    pub locale: Option<String>,
+    /// `client_id`s or names of verifiers the user is not warned about
+    #[serde(default)]
+    pub known_verifiers: Vec<String>,
+    /// Privacy weights of claims
+    #[serde(default)]
+    pub scoring: ScoringPolicy,
+    /// Order in which `claim_sets` are tried
+    #[serde(default)]
+    pub claim_set_policy: ClaimSetPolicy,
+    /// Time the database was written in seconds since the epoch, used to check
+    /// the validity of credentials as the matcher has no clock
+    pub timestamp: Option<i64>,
+    /// Credentials offered for issuance if nothing matches
+    #[cfg(feature = "inlineissuance")]
+    #[serde(default)]
+    pub issuance_offers: Vec<super::issuance::IssuanceOffer>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -47,6 +120,28 @@ pub enum Meta {
     SdjwtVc { vct_values: Vec<String> },
 }
 
+/// A single `transaction_data` entry of an OpenID4VP request. The type specific
+/// parameters are kept in `data`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransactionData {
+    pub r#type: String,
+    pub credential_ids: Vec<String>,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+impl FromStr for TransactionData {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Ok(decoded) = base64::prelude::BASE64_URL_SAFE_NO_PAD.decode(s.trim_end_matches('='))
+        else {
+            return Err(ParseError::Invalid);
+        };
+        serde_json::from_slice(&decoded).map_err(|_| ParseError::Invalid)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct TrustedAuthority {
     pub r#type: String,
@@ -54,8 +149,13 @@ pub struct TrustedAuthority {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Credential {
     DummyCredential(serde_json::Value),
+    /// Database entry of an mdoc together with its decoded `nameSpaces`
+    IssuerSigned(serde_json::Value, ciborium::Value),
+    /// Database entry of an SD-JWT VC together with its decoded claims
+    SdJwt(serde_json::Value, serde_json::Value),
 }
 
 #[derive(Clone, Debug)]
@@ -67,11 +167,72 @@ pub struct CredentialOptions {
 pub struct Disclosure {
     pub credential: Credential,
     pub claims_queries: Vec<ClaimsQuery>,
+    /// Index of the presented claim set in the credential query's `claim_sets`
+    pub claim_set: Option<usize>,
+    /// Position of the claim set in the order of the claim set policy
+    pub preference: usize,
+}
+
+/// What the user picked with an entry, returned to the wallet in its id
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    pub provider_index: usize,
+    pub claim_set: Option<usize>,
+    /// Optional credential sets presented along with the credential
+    pub optional: Vec<OptionalSelection>,
+}
+
+/// A credential of an optional credential set the user chose to present
+#[derive(Clone, Debug)]
+pub struct OptionalSelection {
+    /// Index of the credential set in the query's `credential_sets`
+    pub credential_set: usize,
+    /// `id` of the credential query
+    pub credential_query: String,
+    pub credential_id: String,
+    pub claim_set: Option<usize>,
+}
+
+impl Selection {
+    /// `{"provider_idx": 0, "id": "1"}`, with `claim_set` and `optional` only
+    /// if there are any
+    pub fn to_json(&self, credential_id: &str) -> Value {
+        let mut id = serde_json::json!({
+            "provider_idx": self.provider_index,
+            "id": credential_id
+        });
+        if let Some(claim_set) = self.claim_set {
+            id["claim_set"] = Value::from(claim_set);
+        }
+        if !self.optional.is_empty() {
+            id["optional"] = self
+                .optional
+                .iter()
+                .map(|a| {
+                    let mut optional = serde_json::json!({
+                        "credential_set": a.credential_set,
+                        "credential_query": a.credential_query,
+                        "id": a.credential_id
+                    });
+                    if let Some(claim_set) = a.claim_set {
+                        optional["claim_set"] = Value::from(claim_set);
+                    }
+                    optional
+                })
+                .collect();
+        }
+        id
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct CredentialSetOption {
-    pub purpose: Option<String>,
+    /// `purpose` of the credential set query, a string or a localized object
+    pub purpose: Option<Value>,
+    /// Optional credential sets are only presented if the user opts in
+    pub required: bool,
+    /// Index of the credential set in the query's `credential_sets`
+    pub index: usize,
     pub set_options: Vec<Vec<SetOption>>,
 }
 #[derive(Clone, Debug)]
@@ -101,6 +262,7 @@ pub struct ClaimsQuery {
     pub id: Option<String>,
     pub path: Pointer,
     pub values: Option<Vec<Value>>,
+    pub intent_to_retain: Option<bool>,
 }
 
 impl ClaimsQuery {
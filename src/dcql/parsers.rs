@@ -30,85 +30,231 @@ use crate::credman::return_error;
 
 pub static DEBUG: OnceLock<bool> = OnceLock::new();
 pub static PARSER: OnceLock<Box<dyn Parser>> = OnceLock::new();
+pub static CONFIG: OnceLock<WalletConfig> = OnceLock::new();
 
 use super::{
-    claims_pointer::selector,
-    models::{Credential, PointerPart},
+    claims_pointer::{selector, Selector},
+    i18n::{self, localized, Message},
+    masking::mask,
+    mdoc,
+    models::{Credential, PointerPart, Selection, WalletConfig},
+    render::{self, render},
+    sdjwt,
 };
 
 pub trait Parser: ParseCredential + ResultFormat {
     fn path_transform(&self, path: &[PointerPart]) -> Vec<PointerPart>;
+    /// Whether `node` of the claims tree is a value shown as a single field
+    fn is_value(&self, node: &Value) -> bool;
+
+    /// Expands a requested claim into the fields to show. `null` wildcards are
+    /// resolved to every array element, and objects and arrays are descended
+    /// into so every disclosed value gets its own row.
+    fn get_fields(
+        &self,
+        path: &[PointerPart],
+        label: String,
+        credential: &Credential,
+    ) -> Vec<(String, Option<String>)> {
+        let data = credential.get_claims();
+        let concrete = match path.to_vec().resolve_ptr(data.clone()) {
+            Ok(pointers) if !pointers.is_empty() => pointers,
+            _ => vec![path.to_vec()],
+        };
+        let numbered = concrete.len() > 1;
+        let mut fields = vec![];
+        for (i, ptr) in concrete.iter().enumerate() {
+            let mut label = self
+                .get_display_name(ptr, credential)
+                .unwrap_or_else(|| label.clone());
+            if numbered {
+                label = format!("{label} {}", i + 1);
+            }
+            expand_field(self, ptr, label, credential, &data, &mut fields);
+        }
+        fields
+    }
+}
+
+fn expand_field<P: Parser + ?Sized>(
+    format: &P,
+    path: &[PointerPart],
+    label: String,
+    credential: &Credential,
+    data: &Value,
+    fields: &mut Vec<(String, Option<String>)>,
+) {
+    let node = selector(path)(data).ok().and_then(|a| a.into_iter().next());
+    let children: Vec<(PointerPart, String)> = match &node {
+        Some(node)
+            if render::has_renderer(path)
+                || render::is_binary(path, node)
+                || format.is_value(node) =>
+        {
+            vec![]
+        }
+        Some(Value::Object(o)) => o
+            .keys()
+            .map(|key| {
+                let part = PointerPart::String(key.to_string());
+                let mut p = path.to_vec();
+                p.push(part.clone());
+                let name = format
+                    .get_display_name(&p, credential)
+                    .unwrap_or_else(|| key.to_string());
+                (part, name)
+            })
+            .collect(),
+        Some(Value::Array(a)) => (0..a.len())
+            .map(|i| (PointerPart::Index(i as u64), (i + 1).to_string()))
+            .collect(),
+        _ => vec![],
+    };
+    if children.is_empty() {
+        fields.push((label, format.get_value(path, credential)));
+        return;
+    }
+    for (part, name) in children {
+        let mut p = path.to_vec();
+        p.push(part);
+        expand_field(
+            format,
+            &p,
+            format!("{label} › {name}"),
+            credential,
+            data,
+            fields,
+        );
+    }
 }
 
 impl Parser for UbiqueWalletDatabaseFormat {
     fn path_transform(&self, path: &[PointerPart]) -> Vec<PointerPart> {
         path.to_vec()
     }
+    fn is_value(&self, node: &Value) -> bool {
+        !node.is_object() && !node.is_array()
+    }
 }
+/// Every value is stored together with its display name, so the wrapping
+/// object is the field.
 impl Parser for CMWalletDatabaseFormat {
     fn path_transform(&self, path: &[PointerPart]) -> Vec<PointerPart> {
         let mut p = path.to_vec();
         p.push(PointerPart::String("value".to_string()));
         p
     }
+    fn is_value(&self, node: &Value) -> bool {
+        node.get("value").is_some()
+    }
 }
 
 pub trait ParseCredential: Any + Send + Sync {
     fn parse(&self, input: &str) -> Option<Vec<Credential>>;
     fn set_debug(&self, input: &str);
+    fn set_config(&self, input: &str);
 }
 pub trait ResultFormat: Any + Send + Sync {
-    fn id(&self, credential_id: &str, provider_index: usize) -> String;
-    fn get_value(&self, path: &[PointerPart], data: &Value) -> Option<String>;
-    fn get_display_name(&self, path: &[PointerPart], data: &Value) -> Option<String>;
+    /// Entry id returned to the wallet, identifying the credential and what
+    /// the user picked
+    fn id(&self, credential_id: &str, selection: &Selection) -> String;
+    /// Rendered value of the claim at `path`, masked according to the credential's
+    /// masking policy
+    fn get_value(&self, path: &[PointerPart], credential: &Credential) -> Option<String>;
+    fn get_display_name(&self, path: &[PointerPart], credential: &Credential) -> Option<String>;
+    /// Locale of the UI strings, from the wallet config or the host
+    fn locale(&self) -> Option<String> {
+        i18n::locale()
+    }
+    fn message(&self, message: Message) -> &'static str {
+        i18n::message(message, self.locale().as_deref())
+    }
 }
 
 pub struct CMWalletDatabaseFormat;
 pub struct UbiqueWalletDatabaseFormat;
 
 impl ResultFormat for UbiqueWalletDatabaseFormat {
-    fn id(&self, credential_id: &str, provider_index: usize) -> String {
-        json!({
-            "provider_idx": provider_index,
-            "id": credential_id
-        })
-        .to_string()
+    fn id(&self, credential_id: &str, selection: &Selection) -> String {
+        selection.to_json(credential_id).to_string()
     }
 
-    fn get_value(&self, path: &[PointerPart], data: &Value) -> Option<String> {
+    fn get_value(&self, path: &[PointerPart], credential: &Credential) -> Option<String> {
+        let data = credential.get_claims();
         let s = selector(path);
-        let v = s(data).ok()?;
+        let v = s(&data).ok()?;
         let part = v.first()?;
-        match &part {
-            Value::String(s) => Some(s.to_string()),
-            Value::Bool(t) => {
-                if *t {
-                    Some(String::from("✓"))
-                } else {
-                    Some(String::from("❌"))
-                }
-            }
-            Value::Object(o) => Some(serde_json::to_string_pretty(&o).ok()?),
-            Value::Array(a) => Some(serde_json::to_string_pretty(&a).ok()?),
-            Value::Number(a) => Some(a.to_string()),
-            _ => None,
+        let value = render(path, part, self.locale().as_deref())?;
+        Some(mask(value, credential.get_masking_policy(path)))
+    }
+
+    fn get_display_name(&self, path: &[PointerPart], credential: &Credential) -> Option<String> {
+        credential.get_claim_label(path, self.locale().as_deref())
+    }
+}
+/// Builds a credential from a database entry, decoding a stored raw SD-JWT or
+/// mdoc. With `wrap` the decoded claims are stored in the CMWallet format.
+fn credential_from_entry(entry: Value, wrap: bool) -> Credential {
+    if entry.get("issuer_signed").is_some() {
+        return mdoc::expand_entry(entry, wrap);
+    }
+    sdjwt::expand_entry(entry, wrap)
+}
+
+/// Stores every claim as `{ "value": ... }` like the CMWallet database does.
+/// Arrays are stored as one value, e.g. mdoc byte strings and driving privileges.
+pub fn wrap_values(value: Value) -> Value {
+    match value {
+        Value::Object(o) => {
+            Value::Object(o.into_iter().map(|(k, v)| (k, wrap_values(v))).collect())
         }
+        v => json!({ "value": v }),
     }
+}
 
-    fn get_display_name(&self, path: &[PointerPart], data: &Value) -> Option<String> {
-        None
+/// Like [`wrap_values`], but every array element is stored on its own so it
+/// is shown as a field of its own.
+pub fn wrap_elements(value: Value) -> Value {
+    match value {
+        Value::Object(o) => {
+            Value::Object(o.into_iter().map(|(k, v)| (k, wrap_elements(v))).collect())
+        }
+        Value::Array(a) => Value::Array(a.into_iter().map(wrap_elements).collect()),
+        v => json!({ "value": v }),
     }
 }
+
+/// The wallet config is read from the top level `config` object of the database.
+fn parse_config(input: &str) -> WalletConfig {
+    let Ok(database) = serde_json::from_str::<serde_json::Value>(input) else {
+        return WalletConfig::default();
+    };
+    serde_json::from_value(database["config"].clone()).unwrap_or_default()
+}
+
 impl ParseCredential for UbiqueWalletDatabaseFormat {
     fn parse(&self, input: &str) -> Option<Vec<Credential>> {
-        let Some(arr) = serde_json::from_str::<Vec<serde_json::Value>>(input).ok() else {
-            #[cfg(target_arch = "wasm32")]
-            return_error("could not parse json");
-            return None;
+        // The database is either a plain array of credentials, or an object holding
+        // them in `credentials` next to the wallet config.
+        let arr = match serde_json::from_str::<serde_json::Value>(input) {
+            Ok(Value::Array(arr)) => arr,
+            Ok(Value::Object(mut o)) => match o.remove("credentials") {
+                Some(Value::Array(arr)) => arr,
+                _ => {
+                    #[cfg(target_arch = "wasm32")]
+                    return_error("no credentials in database");
+                    return None;
+                }
+            },
+            _ => {
+                #[cfg(target_arch = "wasm32")]
+                return_error("could not parse json");
+                return None;
+            }
         };
         Some(
             arr.into_iter()
-                .map(|a| Credential::DummyCredential(a))
+                .map(|a| credential_from_entry(a, false))
                 .collect(),
         )
     }
@@ -116,41 +262,34 @@ impl ParseCredential for UbiqueWalletDatabaseFormat {
     fn set_debug(&self, _input: &str) {
         let _ = DEBUG.set(true);
     }
+
+    fn set_config(&self, input: &str) {
+        let _ = CONFIG.set(parse_config(input));
+    }
 }
 
 impl ResultFormat for CMWalletDatabaseFormat {
-    fn id(&self, credential_id: &str, provider_index: usize) -> String {
-        json!({
-            "provider_idx": provider_index,
-            "id": credential_id
-        })
-        .to_string()
+    fn id(&self, credential_id: &str, selection: &Selection) -> String {
+        selection.to_json(credential_id).to_string()
     }
-    fn get_value(&self, path: &[PointerPart], data: &Value) -> Option<String> {
+    fn get_value(&self, path: &[PointerPart], credential: &Credential) -> Option<String> {
+        let data = credential.get_claims();
         let s = selector(path);
-        let v = s(data).ok()?;
+        let v = s(&data).ok()?;
         let part = v.first()?;
-        match &part["value"] {
-            Value::String(s) => Some(s.to_string()),
-            Value::Bool(t) => {
-                if *t {
-                    Some(String::from("✓"))
-                } else {
-                    Some(String::from("❌"))
-                }
-            }
-            Value::Object(o) => Some(serde_json::to_string_pretty(&o).ok()?),
-            Value::Array(a) => Some(serde_json::to_string_pretty(&a).ok()?),
-            Value::Number(a) => Some(a.to_string()),
-            _ => None,
-        }
+        let value = render(path, &part["value"], self.locale().as_deref())?;
+        Some(mask(value, credential.get_masking_policy(path)))
     }
 
-    fn get_display_name(&self, path: &[PointerPart], data: &Value) -> Option<String> {
+    /// `display` is either a string or an object mapping locales to labels
+    fn get_display_name(&self, path: &[PointerPart], credential: &Credential) -> Option<String> {
+        let locale = self.locale();
+        let data = credential.get_claims();
         let s = selector(path);
-        let v = s(data).ok()?;
-        let part = v.first()?;
-        part["display"].as_str().map(|a| a.to_string())
+        let display = s(&data)
+            .ok()
+            .and_then(|v| localized(&v.first()?["display"], locale.as_deref()));
+        display.or_else(|| credential.get_claim_label(path, locale.as_deref()))
     }
 }
 impl ParseCredential for CMWalletDatabaseFormat {
@@ -174,7 +313,7 @@ impl ParseCredential for CMWalletDatabaseFormat {
                     let mut a = a.clone();
                     a["document_type"] = Value::String(doc_type.clone());
                     a["credential_format"] = Value::String("mso_mdoc".to_string());
-                    Credential::DummyCredential(a)
+                    credential_from_entry(a, true)
                 }))
             })
             .flatten()
@@ -192,7 +331,7 @@ impl ParseCredential for CMWalletDatabaseFormat {
                     let mut a = a.clone();
                     a["document_type"] = Value::String(doc_type.clone());
                     a["credential_format"] = Value::String("dc+sd-jwt".to_string());
-                    Credential::DummyCredential(a)
+                    credential_from_entry(a, true)
                 }))
             })
             .flatten()
@@ -214,11 +353,49 @@ impl ParseCredential for CMWalletDatabaseFormat {
         };
         let _ = DEBUG.set(mdocs);
     }
+
+    fn set_config(&self, input: &str) {
+        let _ = CONFIG.set(parse_config(input));
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ParseCredential, UbiqueWalletDatabaseFormat};
+    use serde_json::json;
+
+    use super::{ParseCredential, Parser, UbiqueWalletDatabaseFormat};
+    use crate::dcql::models::{Credential, PointerPart};
+
+    #[test]
+    fn test_get_fields() {
+        let credential = Credential::DummyCredential(json!({
+            "document_type": "urn:eudi:pid:1",
+            "paths": {
+                "address": { "street_address": "Heidestraße 17", "locality": "Köln" },
+                "nationalities": ["DE", "FR"]
+            }
+        }));
+        let format = UbiqueWalletDatabaseFormat;
+        let address = vec![PointerPart::from("address")];
+        assert_eq!(
+            format.get_fields(&address, String::from("address"), &credential),
+            vec![
+                (String::from("Address › City"), Some(String::from("Köln"))),
+                (
+                    String::from("Address › Street"),
+                    Some(String::from("Heidestraße 17"))
+                )
+            ]
+        );
+        let nationalities = vec![PointerPart::from("nationalities"), PointerPart::Null(None)];
+        assert_eq!(
+            format.get_fields(&nationalities, String::from("nationalities"), &credential),
+            vec![
+                (String::from("Nationalities 1"), Some(String::from("DE"))),
+                (String::from("Nationalities 2"), Some(String::from("FR")))
+            ]
+        );
+    }
 
     #[test]
     fn test_ubique_credential_format() {
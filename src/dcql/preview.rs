@@ -0,0 +1,152 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use serde::Deserialize;
+
+use super::models::{ClaimsQuery, CredentialQuery, DcqlQuery, Meta, PointerPart};
+
+/// Request of the legacy `preview` protocol used by older Chrome builds
+#[derive(Deserialize, Debug, Clone)]
+pub struct PreviewRequest {
+    pub selector: PreviewSelector,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PreviewSelector {
+    pub format: Vec<String>,
+    pub doctype: String,
+    #[serde(default)]
+    pub fields: Vec<PreviewField>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewField {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub intent_to_retain: Option<bool>,
+}
+
+/// The selector is translated into a DCQL query with a single credential query.
+impl From<PreviewRequest> for DcqlQuery {
+    fn from(value: PreviewRequest) -> Self {
+        let selector = value.selector;
+        let is_mdoc = selector
+            .format
+            .iter()
+            .any(|a| a == "mdoc" || a == "mso_mdoc");
+        let (format, meta) = if is_mdoc {
+            (
+                String::from("mso_mdoc"),
+                Meta::IsoMdoc {
+                    doctype_value: selector.doctype,
+                },
+            )
+        } else {
+            (
+                String::from("dc+sd-jwt"),
+                Meta::SdjwtVc {
+                    vct_values: vec![selector.doctype],
+                },
+            )
+        };
+        let claims = selector
+            .fields
+            .into_iter()
+            .map(|field| {
+                let mut path = vec![];
+                if is_mdoc {
+                    if let Some(namespace) = field.namespace {
+                        path.push(PointerPart::String(namespace));
+                    }
+                }
+                path.push(PointerPart::String(field.name));
+                ClaimsQuery {
+                    id: None,
+                    path,
+                    values: None,
+                    intent_to_retain: field.intent_to_retain,
+                }
+            })
+            .collect();
+        DcqlQuery {
+            credentials: Some(vec![CredentialQuery {
+                id: String::from("preview"),
+                format,
+                multiple: None,
+                meta: Some(meta),
+                trusted_authorities: None,
+                require_cryptographic_holder_binding: None,
+                claims: Some(claims),
+                claim_sets: None,
+            }]),
+            credential_sets: None,
+            transaction_data: vec![],
+            vp_formats_supported: Default::default(),
+            verifier: None,
+            client_id: None,
+            signed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PreviewRequest;
+    use crate::dcql::models::{DcqlQuery, PointerPart};
+
+    #[test]
+    fn test_preview_selector() {
+        let request = serde_json::from_str::<PreviewRequest>(
+            r#"{
+                "selector": {
+                    "format": ["mdoc"],
+                    "doctype": "org.iso.18013.5.1.mDL",
+                    "fields": [
+                        {
+                            "namespace": "org.iso.18013.5.1",
+                            "name": "family_name",
+                            "intentToRetain": false
+                        },
+                        {
+                            "namespace": "org.iso.18013.5.1",
+                            "name": "age_over_18",
+                            "intentToRetain": true
+                        }
+                    ]
+                },
+                "nonce": "1234",
+                "readerPublicKey": "BNb2..."
+            }"#,
+        )
+        .unwrap();
+        let query = DcqlQuery::from(request);
+        let credential_query = &query.credentials.as_ref().unwrap()[0];
+        assert_eq!(credential_query.format, "mso_mdoc");
+        let claims = credential_query.claims.as_ref().unwrap();
+        assert_eq!(claims[1].intent_to_retain, Some(true));
+        assert_eq!(
+            claims[0].path,
+            vec![
+                PointerPart::from("org.iso.18013.5.1"),
+                PointerPart::from("family_name")
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use std::cmp::Reverse;
+
+use serde_json::Value;
+
+use super::{
+    models::{Credential, CredentialSetOption, DcqlQuery},
+    scoring,
+};
+
+/// Validity of a credential in seconds since the epoch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Validity {
+    pub issued_at: Option<i64>,
+    pub valid_from: Option<i64>,
+    pub valid_until: Option<i64>,
+}
+
+impl Validity {
+    pub fn is_valid(&self, now: i64) -> bool {
+        self.valid_from.is_none_or(|a| a <= now) && self.valid_until.is_none_or(|a| now < a)
+    }
+}
+
+/// Days since 1970-01-01 of a civil date (H. Hinnant)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Seconds since the epoch of a numeric date, a `full-date` or a `tdate`
+fn timestamp(value: &Value) -> Option<i64> {
+    let s = match value {
+        Value::Number(n) => return n.as_i64(),
+        // the cmwallet format stores values next to their display name
+        Value::Object(o) => return timestamp(o.get("value")?),
+        Value::String(s) => s,
+        _ => return None,
+    };
+    let (date, time) = s.split_once('T').unwrap_or((s, "00:00:00Z"));
+    let date = date
+        .splitn(3, '-')
+        .map(|a| a.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [year, month, day] = date[..] else {
+        return None;
+    };
+    let seconds = time
+        .get(..8)?
+        .split(':')
+        .try_fold(0, |acc, a| Some(acc * 60 + a.parse::<i64>().ok()?))?;
+    // fractional seconds are skipped, the zone is either `Z` or `±hh:mm`
+    let zone = time[8..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone.split_at_checked(1) {
+        Some((sign @ ("+" | "-"), offset)) => {
+            let (hours, minutes) = offset.split_once(':')?;
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            if sign == "-" {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => 0,
+    };
+    Some(days_from_civil(year, month, day) * 86400 + seconds - offset)
+}
+
+impl Credential {
+    /// Validity from `issued_at`, `valid_from` and `valid_until` of the database
+    /// entry, otherwise from the `iat`, `nbf` and `exp` claims.
+    pub fn get_validity(&self) -> Validity {
+        let entry = self.entry();
+        let claims = self.get_claims();
+        let get =
+            |key: &str, claim: &str| timestamp(&entry[key]).or_else(|| timestamp(&claims[claim]));
+        Validity {
+            issued_at: get("issued_at", "iat"),
+            valid_from: get("valid_from", "nbf"),
+            valid_until: get("valid_until", "exp"),
+        }
+    }
+
+    /// Whether the user pinned the credential as preferred
+    pub fn is_pinned(&self) -> bool {
+        self.entry()["pinned"].as_bool().unwrap_or(false)
+    }
+}
+
+impl DcqlQuery {
+    /// Orders the credentials of every credential query by the claim set policy and
+    /// the information they disclose, then valid credentials first, pinned
+    /// credentials first and the most recently issued first. `now` is in seconds
+    /// since the epoch, without it validity is not considered.
+    pub fn rank(&self, options: &mut [CredentialSetOption], now: Option<i64>) {
+        let policy = scoring::policy();
+        let set_options = options
+            .iter_mut()
+            .flat_map(|a| a.set_options.iter_mut())
+            .flatten();
+        for set_option in set_options {
+            let claims = self
+                .credentials
+                .iter()
+                .flatten()
+                .find(|a| a.id == set_option.id)
+                .and_then(|a| a.claims.clone())
+                .unwrap_or_default();
+            set_option.options.sort_by_cached_key(|disclosure| {
+                let validity = disclosure.credential.get_validity();
+                (
+                    disclosure.preference,
+                    policy.disclosure_score(disclosure, &claims),
+                    !now.is_none_or(|now| validity.is_valid(now)),
+                    !disclosure.credential.is_pinned(),
+                    Reverse(validity.issued_at),
+                )
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::timestamp;
+    use crate::dcql::models::{Credential, DcqlQuery};
+
+    #[test]
+    fn test_rank() {
+        assert_eq!(timestamp(&json!("1970-01-02")), Some(86400));
+        assert_eq!(
+            timestamp(&json!("2025-01-01T01:00:00.5+01:00")),
+            Some(1735689600)
+        );
+
+        let query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [{
+                "id": "pid",
+                "format": "dc+sd-jwt",
+                "claims": [{ "path": ["given_name"] }]
+            }]
+        }))
+        .unwrap();
+        let credentials = [
+            json!({ "id": "expired", "document_type": "pid", "paths": { "given_name": { "value": "Erika" }, "exp": 1000 } }),
+            json!({ "id": "old", "document_type": "pid", "paths": { "given_name": { "value": "Erika" }, "iat": 1000 } }),
+            json!({ "id": "new", "document_type": "pid", "paths": { "given_name": { "value": "Erika" }, "iat": 2000 } }),
+            json!({ "id": "pinned", "document_type": "pid", "pinned": true, "paths": { "given_name": { "value": "Erika" } } }),
+        ]
+        .into_iter()
+        .map(Credential::DummyCredential)
+        .collect::<Vec<_>>();
+        let mut options = query.select_credentials(credentials);
+        query.rank(&mut options, Some(1500));
+        let ids = options[0].set_options[0][0]
+            .options
+            .iter()
+            .map(|a| a.credential.get_display_metadata(None).id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["pinned", "new", "old", "expired"]);
+    }
+}
@@ -0,0 +1,606 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+
+//! Built-in claim labels for well-known mdoc namespaces and SD-JWT VC types,
+//! used when the database does not provide a label.
+
+use super::i18n::language_index;
+
+/// Labels in the languages of [`super::i18n::LANGUAGES`]
+type Labels = &'static [(&'static str, [&'static str; 4])];
+
+/// Claims shared by the identity documents below
+const COMMON: Labels = &[
+    ("family_name", ["Family name", "Nachname", "Nom", "Cognome"]),
+    ("given_name", ["Given name", "Vorname", "Prénom", "Nome"]),
+    (
+        "birth_date",
+        [
+            "Date of birth",
+            "Geburtsdatum",
+            "Date de naissance",
+            "Data di nascita",
+        ],
+    ),
+    (
+        "birth_place",
+        [
+            "Place of birth",
+            "Geburtsort",
+            "Lieu de naissance",
+            "Luogo di nascita",
+        ],
+    ),
+    (
+        "age_over_12",
+        [
+            "Age over 12",
+            "Älter als 12",
+            "Plus de 12 ans",
+            "Età superiore a 12",
+        ],
+    ),
+    (
+        "age_over_14",
+        [
+            "Age over 14",
+            "Älter als 14",
+            "Plus de 14 ans",
+            "Età superiore a 14",
+        ],
+    ),
+    (
+        "age_over_16",
+        [
+            "Age over 16",
+            "Älter als 16",
+            "Plus de 16 ans",
+            "Età superiore a 16",
+        ],
+    ),
+    (
+        "age_over_18",
+        [
+            "Age over 18",
+            "Älter als 18",
+            "Plus de 18 ans",
+            "Età superiore a 18",
+        ],
+    ),
+    (
+        "age_over_21",
+        [
+            "Age over 21",
+            "Älter als 21",
+            "Plus de 21 ans",
+            "Età superiore a 21",
+        ],
+    ),
+    (
+        "age_over_65",
+        [
+            "Age over 65",
+            "Älter als 65",
+            "Plus de 65 ans",
+            "Età superiore a 65",
+        ],
+    ),
+    ("age_in_years", ["Age", "Alter", "Âge", "Età"]),
+    (
+        "age_birth_year",
+        [
+            "Year of birth",
+            "Geburtsjahr",
+            "Année de naissance",
+            "Anno di nascita",
+        ],
+    ),
+    ("sex", ["Sex", "Geschlecht", "Sexe", "Sesso"]),
+    (
+        "nationality",
+        [
+            "Nationality",
+            "Staatsangehörigkeit",
+            "Nationalité",
+            "Cittadinanza",
+        ],
+    ),
+    ("portrait", ["Portrait", "Porträt", "Portrait", "Ritratto"]),
+    (
+        "issue_date",
+        [
+            "Issue date",
+            "Ausstellungsdatum",
+            "Date de délivrance",
+            "Data di rilascio",
+        ],
+    ),
+    (
+        "expiry_date",
+        [
+            "Expiry date",
+            "Ablaufdatum",
+            "Date d'expiration",
+            "Data di scadenza",
+        ],
+    ),
+    (
+        "issuing_country",
+        [
+            "Issuing country",
+            "Ausstellungsland",
+            "Pays de délivrance",
+            "Paese di rilascio",
+        ],
+    ),
+    (
+        "issuing_authority",
+        [
+            "Issuing authority",
+            "Ausstellende Behörde",
+            "Autorité de délivrance",
+            "Autorità di rilascio",
+        ],
+    ),
+    (
+        "issuing_jurisdiction",
+        [
+            "Issuing jurisdiction",
+            "Ausstellende Gerichtsbarkeit",
+            "Juridiction de délivrance",
+            "Giurisdizione di rilascio",
+        ],
+    ),
+    (
+        "document_number",
+        [
+            "Document number",
+            "Dokumentnummer",
+            "Numéro du document",
+            "Numero del documento",
+        ],
+    ),
+    (
+        "administrative_number",
+        [
+            "Administrative number",
+            "Verwaltungsnummer",
+            "Numéro administratif",
+            "Numero amministrativo",
+        ],
+    ),
+    (
+        "resident_address",
+        ["Address", "Adresse", "Adresse", "Indirizzo"],
+    ),
+    ("resident_street", ["Street", "Strasse", "Rue", "Via"]),
+    (
+        "resident_house_number",
+        ["House number", "Hausnummer", "Numéro", "Numero civico"],
+    ),
+    (
+        "resident_postal_code",
+        [
+            "Postal code",
+            "Postleitzahl",
+            "Code postal",
+            "Codice postale",
+        ],
+    ),
+    ("resident_city", ["City", "Wohnort", "Ville", "Città"]),
+    (
+        "resident_state",
+        ["State", "Bundesland", "État", "Stato federato"],
+    ),
+    (
+        "resident_country",
+        [
+            "Country of residence",
+            "Wohnsitzland",
+            "Pays de résidence",
+            "Paese di residenza",
+        ],
+    ),
+    (
+        "email_address",
+        [
+            "Email address",
+            "E-Mail-Adresse",
+            "Adresse e-mail",
+            "Indirizzo e-mail",
+        ],
+    ),
+    (
+        "mobile_phone_number",
+        [
+            "Mobile phone number",
+            "Mobiltelefonnummer",
+            "Numéro de portable",
+            "Numero di cellulare",
+        ],
+    ),
+];
+
+const MDL: Labels = &[
+    (
+        "driving_privileges",
+        [
+            "Driving privileges",
+            "Fahrberechtigungen",
+            "Catégories de permis",
+            "Categorie di patente",
+        ],
+    ),
+    (
+        "un_distinguishing_sign",
+        [
+            "Distinguishing sign",
+            "Unterscheidungszeichen",
+            "Signe distinctif",
+            "Sigla distintiva",
+        ],
+    ),
+    (
+        "signature_usual_mark",
+        ["Signature", "Unterschrift", "Signature", "Firma"],
+    ),
+    ("height", ["Height", "Grösse", "Taille", "Altezza"]),
+    ("weight", ["Weight", "Gewicht", "Poids", "Peso"]),
+    (
+        "eye_colour",
+        [
+            "Eye colour",
+            "Augenfarbe",
+            "Couleur des yeux",
+            "Colore degli occhi",
+        ],
+    ),
+    (
+        "hair_colour",
+        [
+            "Hair colour",
+            "Haarfarbe",
+            "Couleur des cheveux",
+            "Colore dei capelli",
+        ],
+    ),
+    (
+        "portrait_capture_date",
+        [
+            "Portrait capture date",
+            "Aufnahmedatum des Porträts",
+            "Date de prise du portrait",
+            "Data del ritratto",
+        ],
+    ),
+    (
+        "family_name_national_character",
+        [
+            "Family name (national characters)",
+            "Nachname (nationale Schrift)",
+            "Nom (caractères nationaux)",
+            "Cognome (caratteri nazionali)",
+        ],
+    ),
+    (
+        "given_name_national_character",
+        [
+            "Given name (national characters)",
+            "Vorname (nationale Schrift)",
+            "Prénom (caractères nationaux)",
+            "Nome (caratteri nazionali)",
+        ],
+    ),
+];
+
+const PID: Labels = &[
+    (
+        "family_name_birth",
+        [
+            "Family name at birth",
+            "Geburtsname",
+            "Nom de naissance",
+            "Cognome alla nascita",
+        ],
+    ),
+    (
+        "given_name_birth",
+        [
+            "Given name at birth",
+            "Vorname bei Geburt",
+            "Prénom de naissance",
+            "Nome alla nascita",
+        ],
+    ),
+    (
+        "birth_country",
+        [
+            "Country of birth",
+            "Geburtsland",
+            "Pays de naissance",
+            "Paese di nascita",
+        ],
+    ),
+    (
+        "birth_state",
+        [
+            "State of birth",
+            "Geburtsbundesland",
+            "État de naissance",
+            "Stato di nascita",
+        ],
+    ),
+    (
+        "birth_city",
+        [
+            "City of birth",
+            "Geburtsstadt",
+            "Ville de naissance",
+            "Città di nascita",
+        ],
+    ),
+    ("gender", ["Gender", "Geschlecht", "Genre", "Genere"]),
+    (
+        "issuance_date",
+        [
+            "Issue date",
+            "Ausstellungsdatum",
+            "Date de délivrance",
+            "Data di rilascio",
+        ],
+    ),
+    (
+        "birthdate",
+        [
+            "Date of birth",
+            "Geburtsdatum",
+            "Date de naissance",
+            "Data di nascita",
+        ],
+    ),
+    (
+        "nationalities",
+        [
+            "Nationalities",
+            "Staatsangehörigkeiten",
+            "Nationalités",
+            "Cittadinanze",
+        ],
+    ),
+    (
+        "place_of_birth",
+        [
+            "Place of birth",
+            "Geburtsort",
+            "Lieu de naissance",
+            "Luogo di nascita",
+        ],
+    ),
+    (
+        "place_of_birth.locality",
+        [
+            "Place of birth",
+            "Geburtsort",
+            "Lieu de naissance",
+            "Luogo di nascita",
+        ],
+    ),
+    (
+        "place_of_birth.country",
+        [
+            "Country of birth",
+            "Geburtsland",
+            "Pays de naissance",
+            "Paese di nascita",
+        ],
+    ),
+    ("address", ["Address", "Adresse", "Adresse", "Indirizzo"]),
+    (
+        "address.formatted",
+        ["Address", "Adresse", "Adresse", "Indirizzo"],
+    ),
+    (
+        "address.street_address",
+        ["Street", "Strasse", "Rue", "Via"],
+    ),
+    (
+        "address.house_number",
+        ["House number", "Hausnummer", "Numéro", "Numero civico"],
+    ),
+    (
+        "address.postal_code",
+        [
+            "Postal code",
+            "Postleitzahl",
+            "Code postal",
+            "Codice postale",
+        ],
+    ),
+    ("address.locality", ["City", "Wohnort", "Ville", "Città"]),
+    ("address.region", ["Region", "Region", "Région", "Regione"]),
+    (
+        "address.country",
+        [
+            "Country of residence",
+            "Wohnsitzland",
+            "Pays de résidence",
+            "Paese di residenza",
+        ],
+    ),
+    (
+        "age_equal_or_over.18",
+        [
+            "Age over 18",
+            "Älter als 18",
+            "Plus de 18 ans",
+            "Età superiore a 18",
+        ],
+    ),
+    (
+        "age_equal_or_over.21",
+        [
+            "Age over 21",
+            "Älter als 21",
+            "Plus de 21 ans",
+            "Età superiore a 21",
+        ],
+    ),
+    ("picture", ["Portrait", "Porträt", "Portrait", "Ritratto"]),
+    (
+        "date_of_issuance",
+        [
+            "Issue date",
+            "Ausstellungsdatum",
+            "Date de délivrance",
+            "Data di rilascio",
+        ],
+    ),
+    (
+        "date_of_expiry",
+        [
+            "Expiry date",
+            "Ablaufdatum",
+            "Date d'expiration",
+            "Data di scadenza",
+        ],
+    ),
+];
+
+const PHOTO_ID: Labels = &[
+    (
+        "family_name_unicode",
+        ["Family name", "Nachname", "Nom", "Cognome"],
+    ),
+    (
+        "given_name_unicode",
+        ["Given name", "Vorname", "Prénom", "Nome"],
+    ),
+    (
+        "issuing_authority_unicode",
+        [
+            "Issuing authority",
+            "Ausstellende Behörde",
+            "Autorité de délivrance",
+            "Autorità di rilascio",
+        ],
+    ),
+    (
+        "resident_address_unicode",
+        ["Address", "Adresse", "Adresse", "Indirizzo"],
+    ),
+    (
+        "resident_city_unicode",
+        ["City", "Wohnort", "Ville", "Città"],
+    ),
+    (
+        "person_id",
+        [
+            "Personal number",
+            "Personennummer",
+            "Numéro personnel",
+            "Numero personale",
+        ],
+    ),
+    (
+        "birth_country",
+        [
+            "Country of birth",
+            "Geburtsland",
+            "Pays de naissance",
+            "Paese di nascita",
+        ],
+    ),
+];
+
+const HEALTH_ID: Labels = &[
+    (
+        "health_insurance_id",
+        [
+            "Health insurance number",
+            "Krankenversicherungsnummer",
+            "Numéro d'assurance maladie",
+            "Numero di assicurazione malattia",
+        ],
+    ),
+    (
+        "affiliation_country",
+        [
+            "Country of affiliation",
+            "Versicherungsland",
+            "Pays d'affiliation",
+            "Paese di affiliazione",
+        ],
+    ),
+    (
+        "wallet_e_prescription_code",
+        [
+            "E-prescription code",
+            "E-Rezept-Code",
+            "Code d'ordonnance électronique",
+            "Codice di ricetta elettronica",
+        ],
+    ),
+];
+
+/// mdoc namespaces and SD-JWT VC types with built-in labels
+const REGISTRY: &[(&str, &[Labels])] = &[
+    ("org.iso.18013.5.1", &[MDL, COMMON]),
+    ("eu.europa.ec.eudi.pid.1", &[PID, COMMON]),
+    ("org.iso.23220.1", &[PHOTO_ID, COMMON]),
+    ("urn:eu.europa.ec.eudi:pid:1", &[PID, COMMON]),
+    ("urn:eudi:pid:1", &[PID, COMMON]),
+    (
+        "https://example.eudi.ec.europa.eu/hiid/1",
+        &[HEALTH_ID, COMMON],
+    ),
+];
+
+/// Looks up the label of `claim` (nested claims joined by `.`) in the namespace or
+/// type `key`. Unknown languages fall back to English.
+pub fn claim_label(key: &str, claim: &str, locale: Option<&str>) -> Option<&'static str> {
+    let (_, tables) = REGISTRY.iter().find(|(k, _)| *k == key)?;
+    let (_, labels) = tables
+        .iter()
+        .flat_map(|a| a.iter())
+        .find(|(c, _)| *c == claim)?;
+    Some(labels[language_index(locale)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::claim_label;
+
+    #[test]
+    fn test_claim_label() {
+        assert_eq!(
+            claim_label("org.iso.18013.5.1", "driving_privileges", Some("de-CH")),
+            Some("Fahrberechtigungen")
+        );
+        assert_eq!(
+            claim_label("eu.europa.ec.eudi.pid.1", "family_name", Some("it")),
+            Some("Cognome")
+        );
+        assert_eq!(
+            claim_label("urn:eudi:pid:1", "address.street_address", Some("pt")),
+            Some("Street")
+        );
+        assert_eq!(claim_label("org.iso.18013.5.1", "unknown", None), None);
+    }
+}
@@ -0,0 +1,385 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use base64::Engine;
+use serde_json::Value;
+
+use super::{
+    i18n::{message, Message},
+    models::PointerPart,
+};
+
+type Renderer = fn(&Value, Option<&str>) -> Option<String>;
+
+const MDL: &str = "org.iso.18013.5.1";
+const PID: &str = "eu.europa.ec.eudi.pid.1";
+const PHOTO_ID: &str = "org.iso.23220.1";
+
+/// Renderers keyed by namespace and element identifier. Entries without a
+/// namespace apply to top-level SD-JWT VC claims.
+const RENDERERS: &[(Option<&str>, &str, Renderer)] = &[
+    (Some(MDL), "driving_privileges", driving_privileges),
+    (Some(MDL), "sex", sex),
+    (Some(MDL), "birth_date", date),
+    (Some(MDL), "issue_date", date),
+    (Some(MDL), "expiry_date", date),
+    (Some(MDL), "portrait_capture_date", date),
+    (Some(PID), "sex", sex),
+    (Some(PID), "birth_date", date),
+    (Some(PID), "issuance_date", date),
+    (Some(PID), "expiry_date", date),
+    (Some(PHOTO_ID), "sex", sex),
+    (Some(PHOTO_ID), "birth_date", date),
+    (Some(PHOTO_ID), "issue_date", date),
+    (Some(PHOTO_ID), "expiry_date", date),
+    (None, "sex", sex),
+    (None, "birthdate", date),
+    (None, "date_of_issuance", date),
+    (None, "date_of_expiry", date),
+    (None, "iat", date),
+    (None, "nbf", date),
+    (None, "exp", date),
+];
+
+fn renderer(path: &[PointerPart]) -> Option<Renderer> {
+    let (namespace, element) = match path {
+        [PointerPart::String(namespace), PointerPart::String(element)] => {
+            (Some(namespace.as_str()), element.as_str())
+        }
+        [PointerPart::String(element)] => (None, element.as_str()),
+        _ => return None,
+    };
+    RENDERERS
+        .iter()
+        .find(|(n, e, _)| *n == namespace && *e == element)
+        .map(|(_, _, renderer)| *renderer)
+}
+
+/// Whether the claim at `path` has a dedicated renderer and is shown as a whole
+pub fn has_renderer(path: &[PointerPart]) -> bool {
+    renderer(path).is_some()
+}
+
+/// Renders the value of the claim at `path` for display, using a renderer for
+/// the namespace and element if there is one.
+pub fn render(path: &[PointerPart], value: &Value, locale: Option<&str>) -> Option<String> {
+    binary(path, value, locale)
+        .or_else(|| renderer(path).and_then(|renderer| renderer(value, locale)))
+        .or_else(|| plain(value, locale))
+}
+
+fn plain(value: &Value, locale: Option<&str>) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_string()),
+        Value::Bool(t) => {
+            if *t {
+                Some(message(Message::Yes, locale).to_string())
+            } else {
+                Some(message(Message::No, locale).to_string())
+            }
+        }
+        Value::Object(o) => Some(serde_json::to_string_pretty(&o).ok()?),
+        Value::Array(a) => Some(serde_json::to_string_pretty(&a).ok()?),
+        Value::Number(a) => Some(a.to_string()),
+        _ => None,
+    }
+}
+
+/// ISO/IEC 5218 codes
+fn sex(value: &Value, locale: Option<&str>) -> Option<String> {
+    let code = match value {
+        Value::Number(n) => n.as_u64()?,
+        Value::String(s) => s.parse().ok()?,
+        _ => return None,
+    };
+    let sex = match code {
+        0 => Message::SexNotKnown,
+        1 => Message::SexMale,
+        2 => Message::SexFemale,
+        9 => Message::SexNotApplicable,
+        _ => return None,
+    };
+    Some(message(sex, locale).to_string())
+}
+
+/// `full-date` values are kept, `tdate` values are shortened to minutes and
+/// numeric dates are read as seconds since the epoch.
+fn date(value: &Value, _locale: Option<&str>) -> Option<String> {
+    match value {
+        Value::String(s) => Some(tdate(s).unwrap_or_else(|| s.to_string())),
+        Value::Number(n) => epoch_seconds(n.as_i64()?),
+        _ => None,
+    }
+}
+
+fn tdate(s: &str) -> Option<String> {
+    let (date, time) = s.split_once('T')?;
+    let hours_minutes = time.get(..5)?;
+    let zone = if time.ends_with('Z') {
+        "UTC"
+    } else {
+        let offset = time.get(8..)?;
+        &offset[offset.find(['+', '-'])?..]
+    };
+    Some(format!("{date} {hours_minutes} {zone}"))
+}
+
+fn epoch_seconds(seconds: i64) -> Option<String> {
+    let days = seconds.div_euclid(86400);
+    let second_of_day = seconds.rem_euclid(86400);
+    // civil date from days since 1970-01-01 (H. Hinnant)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    Some(format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        second_of_day / 3600,
+        second_of_day % 3600 / 60
+    ))
+}
+
+/// One line per vehicle category, e.g. `B: 2024-01-15 – 2039-01-14, 78`
+fn driving_privileges(value: &Value, locale: Option<&str>) -> Option<String> {
+    let lines = value
+        .as_array()?
+        .iter()
+        .map(|privilege| {
+            let mut line = privilege["vehicle_category_code"]
+                .as_str()
+                .unwrap_or("?")
+                .to_string();
+            let validity = [&privilege["issue_date"], &privilege["expiry_date"]]
+                .map(|a| date(a, locale).unwrap_or_default());
+            if validity.iter().any(|a| !a.is_empty()) {
+                line.push_str(&format!(": {}", validity.join(" – ")));
+            }
+            for code in privilege["codes"].as_array().into_iter().flatten() {
+                let Some(c) = code["code"].as_str() else {
+                    continue;
+                };
+                line.push_str(&format!(", {c}"));
+                let sign = code["sign"].as_str();
+                if let Some(value) = code["value"].as_str() {
+                    match sign {
+                        Some(sign) => line.push_str(&format!(" ({sign} {value})")),
+                        None => line.push_str(&format!(" ({value})")),
+                    }
+                }
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+    Some(lines.join("\n"))
+}
+
+/// Claims holding images, e.g. portraits, signatures and biometric templates
+pub const IMAGE_CLAIMS: [&str; 5] = [
+    "portrait",
+    "picture",
+    "signature_usual_mark",
+    "biometric_template",
+    "enrolment_portrait_image",
+];
+
+fn is_image_claim(path: &[PointerPart]) -> bool {
+    matches!(path.last(), Some(PointerPart::String(element))
+        if IMAGE_CLAIMS.contains(&element.as_str()))
+}
+
+/// Bytes of an image claim: CBOR byte strings show up as arrays of numbers, in
+/// JSON images are base64 encoded, optionally as a `data:` URL. Other claims
+/// only count as images if their bytes start with a known image header.
+fn binary_content(path: &[PointerPart], value: &Value) -> Option<Vec<u8>> {
+    let bytes = match value {
+        Value::Array(a) => a
+            .iter()
+            .map(|a| a.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect::<Option<Vec<_>>>()?,
+        Value::String(s) => {
+            let encoded = match s.strip_prefix("data:") {
+                Some(data_url) => data_url.split_once(";base64,")?.1,
+                None => s,
+            };
+            let encoded = encoded.trim_end_matches('=');
+            base64::prelude::BASE64_STANDARD_NO_PAD
+                .decode(encoded)
+                .or_else(|_| base64::prelude::BASE64_URL_SAFE_NO_PAD.decode(encoded))
+                .ok()?
+        }
+        _ => return None,
+    };
+    (is_image_claim(path) || image_format(&bytes).is_some()).then_some(bytes)
+}
+
+fn image_format(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xff, 0xd8, ..] => Some("JPEG"),
+        [0x89, b'P', b'N', b'G', ..] => Some("PNG"),
+        [0, 0, 0, 0x0c, b'j', b'P', ..] | [0xff, 0x4f, 0xff, 0x51, ..] => Some("JPEG 2000"),
+        [b'G', b'I', b'F', b'8', ..] => Some("GIF"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("WebP"),
+        _ => None,
+    }
+}
+
+/// Whether the claim at `path` holds binary data, which is shown as a whole
+pub fn is_binary(path: &[PointerPart], value: &Value) -> bool {
+    binary_content(path, value).is_some()
+}
+
+/// Placeholder for images, e.g. `Image (JPEG, 12 KB)`
+fn binary(path: &[PointerPart], value: &Value, locale: Option<&str>) -> Option<String> {
+    let bytes = binary_content(path, value)?;
+    let format = image_format(&bytes);
+    let kind = message(Message::Image, locale);
+    let size = if bytes.len() < 1024 {
+        format!("{} {}", bytes.len(), message(Message::Bytes, locale))
+    } else {
+        format!("{} KB", bytes.len().div_ceil(1024))
+    };
+    match format {
+        Some(format) => Some(format!("{kind} ({format}, {size})")),
+        None => Some(format!("{kind} ({size})")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{is_image_claim, render};
+    use crate::dcql::models::PointerPart;
+
+    #[test]
+    fn test_render() {
+        let path = |p: &[&str]| p.iter().map(|a| PointerPart::from(*a)).collect::<Vec<_>>();
+        let privileges = json!([
+            {
+                "vehicle_category_code": "B",
+                "issue_date": "2024-01-15",
+                "expiry_date": "2039-01-14",
+                "codes": [{ "code": "B96", "value": "4250", "sign": "≤" }, { "code": "78" }]
+            },
+            { "vehicle_category_code": "AM" }
+        ]);
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "driving_privileges"]),
+                &privileges,
+                None
+            )
+            .unwrap(),
+            "B: 2024-01-15 – 2039-01-14, B96 (≤ 4250), 78\nAM"
+        );
+        assert_eq!(
+            render(&path(&["org.iso.18013.5.1", "sex"]), &json!(2), None).unwrap(),
+            "Female"
+        );
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "sex"]),
+                &json!(2),
+                Some("de-CH")
+            )
+            .unwrap(),
+            "Weiblich"
+        );
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "issue_date"]),
+                &json!("2025-04-04T11:39:09Z"),
+                None
+            )
+            .unwrap(),
+            "2025-04-04 11:39 UTC"
+        );
+        assert_eq!(
+            render(&path(&["exp"]), &json!(1776166749), None).unwrap(),
+            "2026-04-14 11:39 UTC"
+        );
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "portrait"]),
+                &json!([0xff, 0xd8, 0xff, 0xe0]),
+                None
+            )
+            .unwrap(),
+            "Image (JPEG, 4 bytes)"
+        );
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "portrait_capture_date"]),
+                &json!("2020-01-01"),
+                None
+            )
+            .unwrap(),
+            "2020-01-01"
+        );
+        assert!(!is_image_claim(&path(&[
+            "org.iso.18013.5.1",
+            "portrait_capture_date"
+        ])));
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "signature_usual_mark"]),
+                &json!([]),
+                None
+            )
+            .unwrap(),
+            "Image (0 bytes)"
+        );
+        assert_eq!(
+            render(
+                &path(&["picture"]),
+                &json!("data:image/png;base64,iVBORw0KGgo="),
+                None
+            )
+            .unwrap(),
+            "Image (PNG, 8 bytes)"
+        );
+        // identifiers that happen to be valid base64 are shown as they are
+        assert_eq!(
+            render(&path(&["document_number"]), &json!("T2200012"), None).unwrap(),
+            "T2200012"
+        );
+        assert_eq!(
+            render(&path(&["card_ids"]), &json!([1, 2, 3]), None).unwrap(),
+            "[\n  1,\n  2,\n  3\n]"
+        );
+        assert_eq!(
+            render(&path(&["family_name"]), &json!("Mustermann"), None).unwrap(),
+            "Mustermann"
+        );
+        assert_eq!(
+            render(
+                &path(&["org.iso.18013.5.1", "age_over_18"]),
+                &json!(true),
+                None
+            )
+            .unwrap(),
+            "Yes"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use super::{
+    i18n::{message, Message},
+    models::{DcqlQuery, Pointer},
+    InformationScore,
+};
+
+/// Score from which a single claim counts as sensitive, e.g. birth dates and addresses
+const SENSITIVE_CLAIM_SCORE: usize = 6;
+
+/// Request level risk of presenting a set of claims
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskAssessment {
+    pub score: usize,
+    pub sensitive: bool,
+    pub signed: bool,
+    /// `None` if the wallet does not know any verifiers
+    pub known_verifier: Option<bool>,
+    pub intent_to_retain: bool,
+}
+
+impl DcqlQuery {
+    /// Assesses presenting the claims at `paths` for the credential query
+    /// `credential_id`, given the verifiers the wallet knows.
+    pub fn assess_risk(
+        &self,
+        credential_id: &str,
+        paths: &[Pointer],
+        known_verifiers: &[String],
+    ) -> RiskAssessment {
+        let scores = paths.iter().map(|p| p.score()).collect::<Vec<_>>();
+        let intent_to_retain = self
+            .credentials
+            .iter()
+            .flatten()
+            .filter(|a| a.id == credential_id)
+            .flat_map(|a| a.claims.iter().flatten())
+            .any(|a| a.intent_to_retain == Some(true) && paths.contains(&a.path));
+        let known_verifier = (!known_verifiers.is_empty()).then(|| {
+            [&self.client_id, &self.verifier]
+                .into_iter()
+                .flatten()
+                .any(|a| known_verifiers.contains(a))
+        });
+        RiskAssessment {
+            score: scores.iter().sum(),
+            sensitive: scores.iter().any(|a| *a >= SENSITIVE_CLAIM_SCORE),
+            signed: self.signed,
+            known_verifier,
+            intent_to_retain,
+        }
+    }
+}
+
+impl RiskAssessment {
+    /// Warning shown with the entry if sensitive data goes to a verifier the
+    /// wallet cannot vouch for or who keeps it.
+    pub fn warning(&self, locale: Option<&str>) -> Option<String> {
+        if !self.sensitive {
+            return None;
+        }
+        let reasons = [
+            (!self.signed).then_some(Message::UnsignedRequest),
+            (self.known_verifier == Some(false)).then_some(Message::UnknownVerifier),
+            self.intent_to_retain.then_some(Message::IntentToRetain),
+        ];
+        let reasons = reasons.into_iter().flatten().collect::<Vec<_>>();
+        if reasons.is_empty() {
+            return None;
+        }
+        let lines = std::iter::once(Message::SensitiveRequest)
+            .chain(reasons)
+            .map(|a| message(a, locale))
+            .collect::<Vec<_>>();
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dcql::models::{DcqlQuery, PointerPart};
+
+    #[test]
+    fn test_risk_warning() {
+        let mut query = serde_json::from_str::<DcqlQuery>(
+            r#"{
+                "credentials": [{
+                    "id": "pid",
+                    "format": "dc+sd-jwt",
+                    "claims": [
+                        { "path": ["birth_date"], "intent_to_retain": true },
+                        { "path": ["age_over_18"] }
+                    ]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let birth_date = vec![PointerPart::from("birth_date")];
+        let age_over_18 = vec![PointerPart::from("age_over_18")];
+
+        let risk = query.assess_risk("pid", std::slice::from_ref(&birth_date), &[]);
+        assert!(risk.sensitive && risk.intent_to_retain);
+        let warning = risk.warning(None).unwrap();
+        assert!(warning.contains("not signed"));
+
+        assert_eq!(
+            query.assess_risk("pid", &[age_over_18], &[]).warning(None),
+            None
+        );
+
+        query.signed = true;
+        query.client_id = Some(String::from("x509_san_dns:verifier.example"));
+        let known = [String::from("x509_san_dns:verifier.example")];
+        let risk = query.assess_risk("pid", std::slice::from_ref(&birth_date), &known);
+        assert_eq!(risk.known_verifier, Some(true));
+        assert!(!risk.warning(None).unwrap().contains("not signed"));
+
+        let risk = query.assess_risk("pid", &[birth_date], &[String::from("other")]);
+        assert_eq!(risk.known_verifier, Some(false));
+    }
+}
@@ -0,0 +1,403 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+//! Privacy weights of claims, used to follow the principle of least information
+//! when choosing between claim sets and credentials.
+//!
+//! Claims are identified by their string path parts joined with `.`, e.g.
+//! `org.iso.18013.5.1.portrait` or `address.street_address`. The longest weight
+//! key the claim ends with is used, so `birth_date` applies to every namespace.
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    claims_pointer::selector,
+    models::{ClaimsQuery, Disclosure, PointerPart},
+    parsers::CONFIG,
+    render::IMAGE_CLAIMS,
+};
+
+const MDL: &str = "org.iso.18013.5.1";
+const PID: &str = "eu.europa.ec.eudi.pid.1";
+
+const DEFAULT_WEIGHT: usize = 2;
+
+/// Built-in weights, `None` applies to every namespace and SD-JWT VC
+const WEIGHTS: &[(Option<&str>, &str, usize)] = &[
+    (Some(MDL), "resident_address", 6),
+    (Some(MDL), "resident_city", 2),
+    (Some(MDL), "resident_postal_code", 3),
+    (Some(MDL), "document_number", 4),
+    (Some(PID), "resident_address", 6),
+    (Some(PID), "resident_street", 6),
+    (Some(PID), "resident_house_number", 3),
+    (Some(PID), "resident_city", 2),
+    (Some(PID), "resident_postal_code", 3),
+    (Some(PID), "family_name_birth", 3),
+    (Some(PID), "given_name_birth", 3),
+    (None, "birth_date", 6),
+    (None, "birthdate", 6),
+    (None, "birth_place", 4),
+    (None, "place_of_birth", 4),
+    (None, "personal_administrative_number", 6),
+    (None, "document_number", 4),
+    (None, "nationality", 3),
+    (None, "nationalities", 3),
+    (None, "sex", 3),
+    (None, "address.formatted", 6),
+    (None, "address.street_address", 6),
+    (None, "address.house_number", 3),
+    (None, "address.postal_code", 3),
+    (None, "address.locality", 2),
+    (None, "address.region", 2),
+    (None, "address.country", 2),
+];
+
+/// Claims only revealing whether a statement holds
+const AGE_CLAIMS: [&str; 2] = ["age_over_", "age_equal_or_over"];
+
+/// Weights of claims, configured with `scoring` in the wallet config
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScoringPolicy {
+    /// Weights by claim, see the module documentation for the keys
+    #[serde(default)]
+    pub weights: BTreeMap<String, usize>,
+    /// Weight of claims without a configured or built-in weight
+    #[serde(default = "default_weight")]
+    pub default_weight: usize,
+    /// Configured and built-in weights by key, built on first use
+    #[serde(skip)]
+    entries: OnceLock<Vec<(String, usize)>>,
+}
+
+fn default_weight() -> usize {
+    DEFAULT_WEIGHT
+}
+
+impl Default for ScoringPolicy {
+    fn default() -> Self {
+        ScoringPolicy::built_in()
+    }
+}
+
+impl ScoringPolicy {
+    const fn built_in() -> Self {
+        ScoringPolicy {
+            weights: BTreeMap::new(),
+            default_weight: DEFAULT_WEIGHT,
+            entries: OnceLock::new(),
+        }
+    }
+}
+
+static BUILT_IN_POLICY: ScoringPolicy = ScoringPolicy::built_in();
+
+/// The policy of the wallet config, otherwise the built-in weights
+pub fn policy() -> &'static ScoringPolicy {
+    CONFIG.get().map(|a| &a.scoring).unwrap_or(&BUILT_IN_POLICY)
+}
+
+fn key(path: &[PointerPart]) -> String {
+    path.iter()
+        .filter_map(|p| match p {
+            PointerPart::String(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Whether the claim `key` is `entry` or ends with it at a part boundary
+fn ends_with(key: &str, entry: &str) -> bool {
+    key == entry
+        || key
+            .strip_suffix(entry)
+            .is_some_and(|rest| rest.ends_with('.'))
+}
+
+impl ScoringPolicy {
+    /// Configured weights take precedence over the built-in ones
+    fn entries(&self) -> &[(String, usize)] {
+        self.entries.get_or_init(|| {
+            let built_in = WEIGHTS.iter().map(|(namespace, claim, weight)| {
+                let key = match namespace {
+                    Some(namespace) => format!("{namespace}.{claim}"),
+                    None => claim.to_string(),
+                };
+                (key, *weight)
+            });
+            let configured = self.weights.iter().map(|(k, w)| (k.clone(), *w));
+            configured.chain(built_in).collect()
+        })
+    }
+
+    /// Weight of the claim at `path`. Objects without a weight of their own,
+    /// like `address`, weigh as much as all their known children together.
+    pub fn weight(&self, path: &[PointerPart]) -> usize {
+        let key = key(path);
+        let entries = self.entries();
+        if let Some((_, weight)) = entries
+            .iter()
+            .filter(|(entry, _)| ends_with(&key, entry))
+            .max_by_key(|(entry, _)| entry.len())
+        {
+            return *weight;
+        }
+        let mut children = BTreeMap::new();
+        for (entry, weight) in entries {
+            let child = key
+                .match_indices('.')
+                .map(|(i, _)| &key[i + 1..])
+                .chain([key.as_str()])
+                .find_map(|parent| entry.strip_prefix(parent)?.strip_prefix('.'));
+            if let Some(child) = child {
+                // configured weights come first
+                children.entry(child).or_insert(*weight);
+            }
+        }
+        if !children.is_empty() {
+            return children.values().sum();
+        }
+        if key.split('.').any(|part| IMAGE_CLAIMS.contains(&part)) {
+            8
+        } else if key
+            .split('.')
+            .any(|part| AGE_CLAIMS.iter().any(|a| part.starts_with(a)))
+        {
+            1
+        } else {
+            self.default_weight
+        }
+    }
+
+    /// Weight of what is actually disclosed at `path` of a credential's claims:
+    /// objects and arrays count every value below them.
+    pub fn disclosed(&self, path: &[PointerPart], claims: &Value) -> usize {
+        match selector(path)(claims) {
+            Ok(nodes) if !nodes.is_empty() => nodes
+                .iter()
+                .map(|node| self.node_weight(&mut path.to_vec(), node))
+                .sum(),
+            _ => self.weight(path),
+        }
+    }
+
+    fn node_weight(&self, path: &mut Vec<PointerPart>, node: &Value) -> usize {
+        let child = |path: &mut Vec<PointerPart>, part: PointerPart, child: &Value| {
+            path.push(part);
+            let weight = self.node_weight(path, child);
+            path.pop();
+            weight
+        };
+        match node {
+            // the cmwallet format stores values next to their display name
+            Value::Object(o) if !o.contains_key("value") && !o.is_empty() => o
+                .iter()
+                .map(|(k, v)| child(path, PointerPart::String(k.clone()), v))
+                .sum(),
+            Value::Array(a) if a.iter().any(|v| v.is_object() || v.is_array()) => a
+                .iter()
+                .enumerate()
+                .map(|(i, v)| child(path, PointerPart::Index(i as u64), v))
+                .sum(),
+            _ => self.weight(path),
+        }
+    }
+
+    fn claims_score(&self, claims: &[&ClaimsQuery]) -> usize {
+        claims.iter().map(|a| self.weight(&a.path)).sum()
+    }
+
+    /// Orders claim sets from the least to the most information disclosed.
+    /// Claim sets referencing unknown claims are kept in place relative to each other.
+    pub fn rank_claim_sets(
+        &self,
+        claim_sets: &[Vec<String>],
+        claims: &BTreeMap<String, ClaimsQuery>,
+    ) -> Vec<Vec<String>> {
+        let mut ranked = claim_sets.to_vec();
+        ranked.sort_by_cached_key(|claim_set| {
+            let claims = claim_set
+                .iter()
+                .filter_map(|id| claims.get(id))
+                .collect::<Vec<_>>();
+            self.claims_score(&claims)
+        });
+        ranked
+    }
+
+    /// Information disclosed by presenting `disclosure`, counting the requested
+    /// `claims` if no claim set applies.
+    pub fn disclosure_score(&self, disclosure: &Disclosure, claims: &[ClaimsQuery]) -> usize {
+        let data = disclosure.credential.get_claims();
+        let presented = if disclosure.claims_queries.is_empty() {
+            claims
+        } else {
+            disclosure.claims_queries.as_slice()
+        };
+        presented
+            .iter()
+            .map(|a| self.disclosed(&a.path, &data))
+            .sum()
+    }
+}
+
+/// Order in which the claim sets of a credential query are tried, configured
+/// with `claim_set_policy` in the wallet config
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimSetPolicy {
+    /// The verifier's order, which the spec defines as its preference
+    VerifierPreference,
+    /// The least information first, equal scores in the verifier's order
+    #[default]
+    LeastInformation,
+    /// The verifier's most preferred claim set first, the remaining ones from
+    /// the least information, equal scores in the verifier's order
+    Hybrid,
+}
+
+impl ClaimSetPolicy {
+    pub fn order(
+        &self,
+        scoring: &ScoringPolicy,
+        claim_sets: &[Vec<String>],
+        claims: &BTreeMap<String, ClaimsQuery>,
+    ) -> Vec<Vec<String>> {
+        match self {
+            ClaimSetPolicy::VerifierPreference => claim_sets.to_vec(),
+            ClaimSetPolicy::LeastInformation => scoring.rank_claim_sets(claim_sets, claims),
+            ClaimSetPolicy::Hybrid => {
+                let Some((preferred, rest)) = claim_sets.split_first() else {
+                    return vec![];
+                };
+                let mut ordered = vec![preferred.clone()];
+                ordered.extend(scoring.rank_claim_sets(rest, claims));
+                ordered
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::{ClaimSetPolicy, ScoringPolicy};
+    use crate::dcql::models::{ClaimsQuery, Credential, Disclosure, PointerPart};
+
+    fn path(parts: &[&str]) -> Vec<PointerPart> {
+        parts.iter().map(|a| PointerPart::from(*a)).collect()
+    }
+
+    #[test]
+    fn test_weights() {
+        let policy = ScoringPolicy::default();
+        assert_eq!(policy.weight(&path(&["age_over_18"])), 1);
+        assert_eq!(policy.weight(&path(&["age_equal_or_over", "18"])), 1);
+        assert_eq!(policy.weight(&path(&["family_name"])), 2);
+        // counted once, although it contains both "birth" and "date"
+        assert_eq!(
+            policy.weight(&path(&["org.iso.18013.5.1", "birth_date"])),
+            6
+        );
+        assert_eq!(policy.weight(&path(&["org.iso.18013.5.1", "portrait"])), 8);
+        assert_eq!(
+            policy.weight(&path(&["org.iso.18013.5.1", "portrait_capture_date"])),
+            2
+        );
+        assert_eq!(policy.weight(&path(&["address", "locality"])), 2);
+        assert_eq!(policy.weight(&path(&["address"])), 24);
+
+        let policy = serde_json::from_value::<ScoringPolicy>(json!({
+            "weights": { "address.locality": 5, "org.iso.18013.5.1.birth_date": 1 },
+            "default_weight": 3
+        }))
+        .unwrap();
+        assert_eq!(policy.weight(&path(&["address"])), 27);
+        assert_eq!(
+            policy.weight(&path(&["org.iso.18013.5.1", "birth_date"])),
+            1
+        );
+        assert_eq!(policy.weight(&path(&["birth_date"])), 6);
+        assert_eq!(policy.weight(&path(&["family_name"])), 3);
+    }
+
+    #[test]
+    fn test_ranking() {
+        let policy = ScoringPolicy::default();
+        let claims = serde_json::from_value::<Vec<ClaimsQuery>>(json!([
+            { "id": "a", "path": ["birth_date"] },
+            { "id": "b", "path": ["age_over_18"] },
+            { "id": "c", "path": ["address"] }
+        ]))
+        .unwrap();
+        let claims_map = claims
+            .iter()
+            .map(|a| (a.id.clone().unwrap(), a.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let claim_sets = vec![
+            vec![String::from("c")],
+            vec![String::from("a")],
+            vec![String::from("b")],
+        ];
+        assert_eq!(
+            policy.rank_claim_sets(&claim_sets, &claims_map),
+            vec![
+                vec![String::from("b")],
+                vec![String::from("a")],
+                vec![String::from("c")]
+            ]
+        );
+
+        let credential = |address: serde_json::Value| Disclosure {
+            credential: Credential::DummyCredential(json!({ "paths": { "address": address } })),
+            claims_queries: vec![],
+            claim_set: None,
+            preference: 0,
+        };
+        // the verifier prefers more information than the scores would
+        let claim_sets = vec![
+            vec![String::from("a"), String::from("c")],
+            vec![String::from("c")],
+            vec![String::from("a")],
+            vec![String::from("b")],
+        ];
+        let policy = |name: &str| {
+            serde_json::from_value::<ClaimSetPolicy>(json!(name))
+                .unwrap()
+                .order(&ScoringPolicy::default(), &claim_sets, &claims_map)
+        };
+        let ids = |name: &str| policy(name).iter().map(|a| a.join("+")).collect::<Vec<_>>();
+        assert_eq!(policy("verifier_preference"), claim_sets);
+        assert_eq!(ids("hybrid"), ["a+c", "b", "a", "c"]);
+        assert_eq!(ids("least_information"), ["b", "a", "c", "a+c"]);
+
+        let policy = ScoringPolicy::default();
+        let full = credential(json!({ "street_address": "Main St", "locality": "Bern" }));
+        let locality = credential(json!({ "locality": "Bern" }));
+        assert_eq!(policy.disclosure_score(&full, &claims[2..]), 8);
+        assert_eq!(policy.disclosure_score(&locality, &claims[2..]), 2);
+    }
+}
@@ -0,0 +1,260 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use super::{
+    models::{Credential, Pointer, PointerPart},
+    parsers::wrap_elements,
+};
+
+/// Claims of an SD-JWT VC with all disclosures applied
+#[derive(Debug, Clone)]
+pub struct SdJwt {
+    pub header: Value,
+    pub claims: Value,
+    /// Paths of all claims that were disclosed by a disclosure
+    pub selectively_disclosable: Vec<Pointer>,
+}
+
+enum Disclosed {
+    Property(String, Value),
+    Element(Value),
+}
+
+pub fn sha256_digest(input: &[u8]) -> String {
+    base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(input))
+}
+
+fn decode_part(part: &str) -> Option<Value> {
+    let decoded = base64::prelude::BASE64_URL_SAFE_NO_PAD
+        .decode(part.trim_end_matches('='))
+        .ok()?;
+    serde_json::from_slice(&decoded).ok()
+}
+
+/// Decodes an issued SD-JWT (`<jwt>~<disclosure>~...~`) and reconstructs its
+/// claims tree. Digests without a matching disclosure are decoys and dropped.
+pub fn decode(compact: &str) -> Option<SdJwt> {
+    let mut parts = compact.split('~');
+    let jwt = parts.next()?.split('.').collect::<Vec<_>>();
+    if jwt.len() != 3 {
+        return None;
+    }
+    let header = decode_part(jwt[0])?;
+    let payload = decode_part(jwt[1])?;
+    if payload["_sd_alg"].as_str().unwrap_or("sha-256") != "sha-256" {
+        return None;
+    }
+    let mut disclosures = BTreeMap::new();
+    // a trailing key binding JWT is not a disclosure
+    for disclosure in parts.filter(|a| !a.is_empty() && !a.contains('.')) {
+        let Some(Value::Array(mut decoded)) = decode_part(disclosure) else {
+            return None;
+        };
+        let disclosed = match decoded.len() {
+            3 => {
+                let value = decoded.remove(2);
+                let Value::String(name) = decoded.remove(1) else {
+                    return None;
+                };
+                Disclosed::Property(name, value)
+            }
+            2 => Disclosed::Element(decoded.remove(1)),
+            _ => return None,
+        };
+        disclosures.insert(sha256_digest(disclosure.as_bytes()), disclosed);
+    }
+    let mut selectively_disclosable = vec![];
+    let mut claims = resolve(&payload, &[], &disclosures, &mut selectively_disclosable);
+    if let Some(claims) = claims.as_object_mut() {
+        claims.remove("_sd_alg");
+    }
+    Some(SdJwt {
+        header,
+        claims,
+        selectively_disclosable,
+    })
+}
+
+fn resolve(
+    value: &Value,
+    path: &[PointerPart],
+    disclosures: &BTreeMap<String, Disclosed>,
+    selectively_disclosable: &mut Vec<Pointer>,
+) -> Value {
+    let child_path = |part: PointerPart| {
+        let mut p = path.to_vec();
+        p.push(part);
+        p
+    };
+    match value {
+        Value::Object(o) => {
+            let mut resolved = Map::new();
+            for (key, v) in o.iter().filter(|(k, _)| k.as_str() != "_sd") {
+                let p = child_path(PointerPart::String(key.to_string()));
+                resolved.insert(
+                    key.to_string(),
+                    resolve(v, &p, disclosures, selectively_disclosable),
+                );
+            }
+            let digests = o.get("_sd").and_then(Value::as_array);
+            for digest in digests.into_iter().flatten().filter_map(Value::as_str) {
+                let Some(Disclosed::Property(name, v)) = disclosures.get(digest) else {
+                    continue;
+                };
+                let p = child_path(PointerPart::String(name.to_string()));
+                let v = resolve(v, &p, disclosures, selectively_disclosable);
+                selectively_disclosable.push(p);
+                resolved.insert(name.to_string(), v);
+            }
+            Value::Object(resolved)
+        }
+        Value::Array(a) => {
+            let mut resolved = vec![];
+            for element in a {
+                let p = child_path(PointerPart::Index(resolved.len() as u64));
+                let digest = element
+                    .as_object()
+                    .filter(|o| o.len() == 1)
+                    .and_then(|o| o.get("..."))
+                    .and_then(Value::as_str);
+                let Some(digest) = digest else {
+                    resolved.push(resolve(element, &p, disclosures, selectively_disclosable));
+                    continue;
+                };
+                let Some(Disclosed::Element(v)) = disclosures.get(digest) else {
+                    continue;
+                };
+                resolved.push(resolve(v, &p, disclosures, selectively_disclosable));
+                selectively_disclosable.push(p);
+            }
+            Value::Array(resolved)
+        }
+        v => v.clone(),
+    }
+}
+
+/// Replaces the stored `sd_jwt` of a database entry by its decoded claims. The
+/// claims are matched on as they are, `paths` holds them for rendering and
+/// `selectively_disclosable` the paths of the disclosed claims.
+pub fn expand_entry(mut entry: Value, wrap: bool) -> Credential {
+    let Some(compact) = entry["sd_jwt"].as_str() else {
+        return Credential::DummyCredential(entry);
+    };
+    let Some(sd_jwt) = decode(compact) else {
+        return Credential::DummyCredential(entry);
+    };
+    if entry["document_type"].is_null() {
+        entry["document_type"] = sd_jwt.claims["vct"].clone();
+    }
+    if entry["credential_format"].is_null() {
+        entry["credential_format"] = Value::String(String::from("dc+sd-jwt"));
+    }
+    if entry["issuer_alg"].is_null() {
+        entry["issuer_alg"] = sd_jwt.header["alg"].clone();
+    }
+    entry["selectively_disclosable"] = Value::Array(
+        sd_jwt
+            .selectively_disclosable
+            .into_iter()
+            .map(|p| Value::Array(p.into_iter().map(Value::from).collect()))
+            .collect(),
+    );
+    entry["paths"] = if wrap {
+        wrap_elements(sd_jwt.claims.clone())
+    } else {
+        sd_jwt.claims.clone()
+    };
+    Credential::SdJwt(entry, sd_jwt.claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use serde_json::{json, Value};
+
+    use super::{decode, expand_entry, sha256_digest};
+    use crate::dcql::models::PointerPart;
+
+    fn encode(value: &Value) -> String {
+        base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(value.to_string())
+    }
+
+    #[test]
+    fn test_decode_disclosures() {
+        let given_name = encode(&json!(["salt1", "given_name", "Erika"]));
+        let street = encode(&json!(["salt2", "street_address", "Heidestraße 17"]));
+        let address = encode(&json!([
+            "salt3",
+            "address",
+            { "_sd": [sha256_digest(street.as_bytes())], "country": "DE" }
+        ]));
+        let nationality = encode(&json!(["salt4", "DE"]));
+        let payload = json!({
+            "vct": "urn:eudi:pid:de:1",
+            "_sd_alg": "sha-256",
+            "_sd": [
+                sha256_digest(given_name.as_bytes()),
+                sha256_digest(address.as_bytes()),
+                "decoy_digest"
+            ],
+            "nationalities": [
+                { "...": "another_decoy" },
+                { "...": sha256_digest(nationality.as_bytes()) },
+                "FR"
+            ]
+        });
+        let compact = format!(
+            "{}.{}.signature~{given_name}~{address}~{street}~{nationality}~",
+            encode(&json!({ "alg": "ES256", "typ": "dc+sd-jwt" })),
+            encode(&payload)
+        );
+        let sd_jwt = decode(&compact).unwrap();
+        assert_eq!(
+            sd_jwt.claims,
+            json!({
+                "vct": "urn:eudi:pid:de:1",
+                "given_name": "Erika",
+                "address": { "country": "DE", "street_address": "Heidestraße 17" },
+                "nationalities": ["DE", "FR"]
+            })
+        );
+        assert!(sd_jwt.selectively_disclosable.contains(&vec![
+            PointerPart::from("address"),
+            PointerPart::from("street_address")
+        ]));
+        assert!(sd_jwt.selectively_disclosable.contains(&vec![
+            PointerPart::from("nationalities"),
+            PointerPart::from(0)
+        ]));
+        assert!(!sd_jwt
+            .selectively_disclosable
+            .contains(&vec![PointerPart::from("vct")]));
+        let credential = expand_entry(json!({ "id": "1", "sd_jwt": compact }), false);
+        assert!(credential.entry()["selectively_disclosable"]
+            .as_array()
+            .unwrap()
+            .contains(&json!(["given_name"])));
+    }
+}
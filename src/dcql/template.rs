@@ -0,0 +1,162 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+//! Entry title and subtitle templates like `{given_name} {family_name}` or
+//! `•••• {card_number|last4}`.
+//!
+//! A placeholder holds one or more claim paths separated by `,` (the first one
+//! present is used), nested claims are separated by `/`. Filters are appended
+//! with `|`. Values are masked according to the credential's masking policy
+//! before filters are applied.
+
+use super::{
+    claims_pointer::selector,
+    masking::mask,
+    models::{Credential, PointerPart},
+    render::render,
+};
+
+fn parse_path(path: &str) -> Vec<PointerPart> {
+    path.trim()
+        .split('/')
+        .map(|part| match part.parse::<u64>() {
+            Ok(i) => PointerPart::Index(i),
+            Err(_) => PointerPart::String(part.to_string()),
+        })
+        .collect()
+}
+
+fn resolve(path: &[PointerPart], credential: &Credential, locale: Option<&str>) -> Option<String> {
+    let claims = credential.get_claims();
+    let value = selector(path)(&claims).ok()?.into_iter().next()?;
+    // the cmwallet format stores values next to their display name
+    let value = match value.get("value") {
+        Some(v) if value.is_object() => v.clone(),
+        _ => value,
+    };
+    if value.is_null() || value.is_object() || value.is_array() {
+        return None;
+    }
+    let value = render(path, &value, locale).filter(|a| !a.trim().is_empty())?;
+    Some(mask(value, credential.get_masking_policy(path)))
+}
+
+fn apply_filter(value: String, filter: &str) -> Option<String> {
+    let value = match filter.trim() {
+        "last4" => {
+            let chars = value
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<Vec<_>>();
+            chars[chars.len().saturating_sub(4)..].iter().collect()
+        }
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        "initial" => format!("{}.", value.chars().next()?),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn placeholder(content: &str, credential: &Credential, locale: Option<&str>) -> Option<String> {
+    let mut parts = content.split('|');
+    let alternatives = parts.next()?;
+    let value = alternatives
+        .split(',')
+        .find_map(|path| resolve(&parse_path(path), credential, locale))?;
+    parts.try_fold(value, apply_filter)
+}
+
+/// Evaluates `template` against the claims of `credential`. Fails if any
+/// placeholder cannot be resolved, so the caller can fall back to a fixed string.
+pub fn evaluate(template: &str, credential: &Credential, locale: Option<&str>) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let end = start + rest[start..].find('}')?;
+        result.push_str(&placeholder(&rest[start + 1..end], credential, locale)?);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Some(result.trim().to_string()).filter(|a| !a.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::evaluate;
+    use crate::dcql::models::Credential;
+
+    #[test]
+    fn test_evaluate() {
+        let credential = Credential::DummyCredential(json!({
+            "paths": {
+                "given_name": "Erika",
+                "family_name": "Mustermann",
+                "card_number": { "value": "5466 0000 5555 2312", "display": "Card number" },
+                "address": { "locality": "Köln" }
+            }
+        }));
+        assert_eq!(
+            evaluate("{given_name} {family_name}", &credential, None).as_deref(),
+            Some("Erika Mustermann")
+        );
+        assert_eq!(
+            evaluate("•••• {card_number|last4}", &credential, None).as_deref(),
+            Some("•••• 2312")
+        );
+        assert_eq!(
+            evaluate(
+                "{nickname,given_name|initial} {address/locality|upper}",
+                &credential,
+                None
+            )
+            .as_deref(),
+            Some("E. KÖLN")
+        );
+        assert_eq!(evaluate("{nickname}", &credential, None), None);
+        assert_eq!(evaluate("{given_name|unknown}", &credential, None), None);
+    }
+
+    #[test]
+    fn test_evaluate_masked() {
+        let credential = Credential::DummyCredential(json!({
+            "paths": {
+                "iban": "DE89 3704 0044 0532 0130 00",
+                "cvv": "123",
+                "card_number": "5466000055552312"
+            },
+            "masking": [{ "path": ["card_number"], "policy": "hide" }]
+        }));
+        assert_eq!(
+            evaluate("IBAN {iban}", &credential, None).as_deref(),
+            Some("IBAN •••• 3000")
+        );
+        assert_eq!(
+            evaluate("{cvv|last4}", &credential, None).as_deref(),
+            Some("••••")
+        );
+        assert_eq!(
+            evaluate("{card_number|last4}", &credential, None).as_deref(),
+            Some("••••")
+        );
+    }
+}
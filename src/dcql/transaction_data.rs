@@ -0,0 +1,137 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use serde_json::Value;
+
+use super::{
+    i18n::{message, Message},
+    models::TransactionData,
+};
+
+/// CSC remote signing authorization for one or more documents
+pub const QES_AUTHORIZATION: &str = "qes_authorization";
+/// Acceptance of the terms for issuing a qualified certificate
+pub const QCERT_CREATION_ACCEPTANCE: &str = "qcert_creation_acceptance";
+
+fn hash_algorithm_name(oid: &str) -> &str {
+    match oid {
+        "2.16.840.1.101.3.4.2.1" => "SHA-256",
+        "2.16.840.1.101.3.4.2.2" => "SHA-384",
+        "2.16.840.1.101.3.4.2.3" => "SHA-512",
+        "2.16.840.1.101.3.4.2.8" => "SHA3-256",
+        "2.16.840.1.101.3.4.2.9" => "SHA3-384",
+        "2.16.840.1.101.3.4.2.10" => "SHA3-512",
+        _ => oid,
+    }
+}
+
+impl TransactionData {
+    fn get_str(&self, key: &str) -> Option<&str> {
+        self.data.get(key).and_then(Value::as_str)
+    }
+
+    /// Fields shown next to the requested claims, so the user sees what they
+    /// are about to authorize.
+    pub fn display_fields(&self, locale: Option<&str>) -> Vec<(String, String)> {
+        let name = |m: Message| message(m, locale).to_string();
+        let mut fields = vec![];
+        match self.r#type.as_str() {
+            QES_AUTHORIZATION => {
+                let default_algorithm = self.get_str("hashAlgorithmOID");
+                let digests = self.data.get("documentDigests").and_then(Value::as_array);
+                for digest in digests.into_iter().flatten() {
+                    if let Some(label) = digest["label"].as_str() {
+                        fields.push((name(Message::Document), label.to_string()));
+                    }
+                    if let Some(hash) = digest["hash"].as_str() {
+                        fields.push((name(Message::DocumentHash), hash.to_string()));
+                    }
+                    if let Some(oid) = digest["hashAlgorithmOID"].as_str().or(default_algorithm) {
+                        fields.push((
+                            name(Message::HashAlgorithm),
+                            hash_algorithm_name(oid).to_string(),
+                        ));
+                    }
+                }
+                if let Some(qualifier) = self.get_str("signatureQualifier") {
+                    fields.push((name(Message::SignatureQualifier), qualifier.to_string()));
+                }
+                if let Some(credential_id) = self.get_str("credentialID") {
+                    fields.push((name(Message::SigningCredential), credential_id.to_string()));
+                }
+            }
+            QCERT_CREATION_ACCEPTANCE => {
+                if let Some(uri) = self.get_str("QC_terms_conditions_uri") {
+                    fields.push((name(Message::TermsAndConditions), uri.to_string()));
+                }
+                if let Some(hash) = self.get_str("QC_hash") {
+                    fields.push((name(Message::TermsHash), hash.to_string()));
+                }
+                if let Some(oid) = self.get_str("QC_hashAlgorithmOID") {
+                    fields.push((
+                        name(Message::HashAlgorithm),
+                        hash_algorithm_name(oid).to_string(),
+                    ));
+                }
+            }
+            _ => {}
+        }
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dcql::models::TransactionData;
+
+    #[test]
+    fn test_qes_authorization_fields() {
+        let transaction_data = serde_json::from_str::<TransactionData>(
+            r#"{
+                "type": "qes_authorization",
+                "credential_ids": ["signing"],
+                "signatureQualifier": "eu_eidas_qes",
+                "documentDigests": [
+                    {
+                        "label": "Contract.pdf",
+                        "hash": "sTOgwOm+474gFj0q0x1iSNspKqbcse4IeiqlDg/HWuI=",
+                        "hashAlgorithmOID": "2.16.840.1.101.3.4.2.1"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let fields = transaction_data.display_fields(None);
+        assert_eq!(
+            fields[0],
+            (String::from("Document"), String::from("Contract.pdf"))
+        );
+        assert_eq!(
+            fields[2],
+            (String::from("Hash algorithm"), String::from("SHA-256"))
+        );
+        assert_eq!(
+            fields[3],
+            (
+                String::from("Signature qualifier"),
+                String::from("eu_eidas_qes")
+            )
+        );
+    }
+}
@@ -0,0 +1,272 @@
+/* Copyright 2025 Ubique Innovation AG
+
+Licensed to the Apache Software Foundation (ASF) under one
+or more contributor license agreements.  See the NOTICE file
+distributed with this work for additional information
+regarding copyright ownership.  The ASF licenses this file
+to you under the Apache License, Version 2.0 (the
+"License"); you may not use this file except in compliance
+with the License.  You may obtain a copy of the License at
+
+  http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing,
+software distributed under the License is distributed on an
+"AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+KIND, either express or implied.  See the License for the
+specific language governing permissions and limitations
+under the License.
+ */
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use super::models::{Pointer, PointerPart};
+
+/// SD-JWT VC type metadata document
+#[derive(Deserialize, Debug, Clone)]
+pub struct TypeMetadata {
+    pub vct: String,
+    pub extends: Option<String>,
+    #[serde(rename = "extends#integrity")]
+    pub extends_integrity: Option<String>,
+    #[serde(default)]
+    pub claims: Vec<ClaimMetadata>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClaimMetadata {
+    pub path: Pointer,
+    #[serde(default)]
+    pub display: Vec<ClaimDisplay>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClaimDisplay {
+    #[serde(alias = "lang")]
+    pub locale: Option<String>,
+    pub label: String,
+}
+
+impl ClaimMetadata {
+    /// `null` in the metadata path stands for any array element
+    pub fn applies_to(&self, path: &[PointerPart]) -> bool {
+        self.path.len() == path.len()
+            && self
+                .path
+                .iter()
+                .zip(path)
+                .all(|(a, b)| a == b || matches!(a, PointerPart::Null(_)))
+    }
+}
+
+/// Picks the label for `locale`, falling back to the same language, English and
+/// finally the first label.
+pub fn select_label<'a>(display: &'a [ClaimDisplay], locale: Option<&str>) -> Option<&'a str> {
+    let language = |l: &str| {
+        l.split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase()
+    };
+    let candidates = [
+        locale.map(|l| l.to_string()),
+        locale.map(language),
+        Some(String::from("en")),
+    ];
+    for candidate in candidates.into_iter().flatten() {
+        if let Some(d) = display.iter().find(|d| {
+            d.locale
+                .as_deref()
+                .is_some_and(|l| l.eq_ignore_ascii_case(&candidate) || language(l) == candidate)
+        }) {
+            return Some(&d.label);
+        }
+    }
+    display.first().map(|d| d.label.as_str())
+}
+
+/// Label of the claim at `path`, looked up along the type chain of a credential.
+pub fn claim_label(
+    path: &[PointerPart],
+    chain: &[String],
+    documents: &[StoredTypeMetadata],
+    locale: Option<&str>,
+) -> Option<String> {
+    chain
+        .iter()
+        .filter_map(|vct| documents.iter().find(|a| &a.metadata.vct == vct))
+        .flat_map(|a| &a.metadata.claims)
+        .filter(|claim| claim.applies_to(path))
+        .find_map(|claim| select_label(&claim.display, locale))
+        .map(|a| a.to_string())
+}
+
+/// A type metadata document as stored in the database. Documents can be stored
+/// as their raw string, which is what integrity values are computed over.
+#[derive(Debug, Clone)]
+pub struct StoredTypeMetadata {
+    pub metadata: TypeMetadata,
+    raw: String,
+}
+
+impl StoredTypeMetadata {
+    pub fn parse(value: &Value) -> Option<Self> {
+        let (metadata, raw) = match value {
+            Value::String(raw) => (serde_json::from_str(raw).ok()?, raw.to_string()),
+            value => (
+                serde_json::from_value(value.clone()).ok()?,
+                value.to_string(),
+            ),
+        };
+        Some(Self { metadata, raw })
+    }
+
+    /// Checks the document against a subresource integrity value, e.g. `sha256-...`
+    pub fn verify_integrity(&self, integrity: &str) -> bool {
+        let raw = self.raw.as_bytes();
+        integrity.split_whitespace().any(|option| {
+            let Some((alg, digest)) = option.split_once('-') else {
+                return false;
+            };
+            let digest = digest.split('?').next().unwrap_or_default();
+            let computed = match alg {
+                "sha256" => base64::prelude::BASE64_STANDARD.encode(Sha256::digest(raw)),
+                "sha384" => base64::prelude::BASE64_STANDARD.encode(Sha384::digest(raw)),
+                "sha512" => base64::prelude::BASE64_STANDARD.encode(Sha512::digest(raw)),
+                _ => return false,
+            };
+            computed == digest
+        })
+    }
+}
+
+/// Resolves `vct` and all types it extends. Types are only added if their
+/// document matches the integrity value of the referencing document.
+pub fn vct_chain(
+    vct: &str,
+    vct_integrity: Option<&str>,
+    documents: &[StoredTypeMetadata],
+) -> Vec<String> {
+    let find = |vct: &str| documents.iter().find(|a| a.metadata.vct == vct);
+    let mut chain = vec![vct.to_string()];
+    let mut document = find(vct);
+    if let (Some(integrity), Some(d)) = (vct_integrity, document) {
+        if !d.verify_integrity(integrity) {
+            return chain;
+        }
+    }
+    while let Some(current) = document {
+        let Some(extends) = &current.metadata.extends else {
+            break;
+        };
+        // guard against cyclic type hierarchies
+        if chain.contains(extends) {
+            break;
+        }
+        document = find(extends);
+        if let Some(integrity) = &current.metadata.extends_integrity {
+            if !document.is_some_and(|a| a.verify_integrity(integrity)) {
+                break;
+            }
+        }
+        chain.push(extends.to_string());
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+
+    use super::{claim_label, vct_chain, StoredTypeMetadata};
+    use crate::dcql::models::PointerPart;
+
+    #[test]
+    fn test_vct_chain() {
+        let base = r#"{"vct":"urn:eudi:pid:1","name":"EU PID"}"#;
+        let integrity = format!(
+            "sha256-{}",
+            base64::prelude::BASE64_STANDARD.encode(Sha256::digest(base.as_bytes()))
+        );
+        let national = json!({
+            "vct": "urn:eudi:pid:de:1",
+            "extends": "urn:eudi:pid:1",
+            "extends#integrity": integrity
+        });
+        let documents = [
+            StoredTypeMetadata::parse(&national).unwrap(),
+            StoredTypeMetadata::parse(&Value::from(base)).unwrap(),
+        ];
+        assert_eq!(
+            vct_chain("urn:eudi:pid:de:1", None, &documents),
+            vec!["urn:eudi:pid:de:1", "urn:eudi:pid:1"]
+        );
+        assert_eq!(
+            vct_chain("urn:eudi:pid:de:1", Some("sha256-invalid"), &documents),
+            vec!["urn:eudi:pid:de:1"]
+        );
+
+        let tampered = [
+            documents[0].clone(),
+            StoredTypeMetadata::parse(&json!({ "vct": "urn:eudi:pid:1", "name": "Other" }))
+                .unwrap(),
+        ];
+        assert_eq!(
+            vct_chain("urn:eudi:pid:de:1", None, &tampered),
+            vec!["urn:eudi:pid:de:1"]
+        );
+        let cyclic = [
+            StoredTypeMetadata::parse(&json!({ "vct": "a", "extends": "b" })).unwrap(),
+            StoredTypeMetadata::parse(&json!({ "vct": "b", "extends": "a" })).unwrap(),
+        ];
+        assert_eq!(vct_chain("a", None, &cyclic), vec!["a", "b"]);
+    }
+    #[test]
+    fn test_claim_label() {
+        let base = json!({
+            "vct": "urn:eudi:pid:1",
+            "claims": [
+                {
+                    "path": ["address", "street_address"],
+                    "display": [
+                        { "locale": "en-US", "label": "Street" },
+                        { "locale": "de-CH", "label": "Strasse" }
+                    ]
+                },
+                {
+                    "path": ["nationalities", null],
+                    "display": [{ "lang": "en", "label": "Nationality" }]
+                }
+            ]
+        });
+        let documents = [
+            StoredTypeMetadata::parse(
+                &json!({ "vct": "urn:eudi:pid:de:1", "extends": "urn:eudi:pid:1" }),
+            )
+            .unwrap(),
+            StoredTypeMetadata::parse(&base).unwrap(),
+        ];
+        let chain = vct_chain("urn:eudi:pid:de:1", None, &documents);
+        let street = [
+            PointerPart::from("address"),
+            PointerPart::from("street_address"),
+        ];
+        assert_eq!(
+            claim_label(&street, &chain, &documents, Some("de")).as_deref(),
+            Some("Strasse")
+        );
+        assert_eq!(
+            claim_label(&street, &chain, &documents, Some("fr-CH")).as_deref(),
+            Some("Street")
+        );
+        let nationality = [PointerPart::from("nationalities"), PointerPart::from(1)];
+        assert_eq!(
+            claim_label(&nationality, &chain, &documents, None).as_deref(),
+            Some("Nationality")
+        );
+    }
+}
@@ -21,12 +21,23 @@ mod credman;
 mod dcql;
 
 // #[cfg(target_arch = "wasm32")]
-use credman::{get_credentials, get_dc_request, return_error, select_credential};
+#[cfg(feature = "inlineissuance")]
+use credman::offer_issuance;
+use credman::{
+    disclaimer, get_credentials, get_dc_request, optional_fields, return_error, select_credential,
+};
+#[cfg(feature = "entrysets")]
+use credman::{select_credential_set, SetMember};
+#[cfg(feature = "inlineissuance")]
+use dcql::issuance::is_unsatisfied;
+use dcql::models::{DcqlQuery, Disclosure, OptionalSelection, Pointer, PointerPart, Selection};
 #[cfg(feature = "cmwallet")]
 use dcql::parsers::CMWalletDatabaseFormat as WalletParser;
 #[cfg(feature = "ubiquewallet")]
 use dcql::parsers::UbiqueWalletDatabaseFormat as WalletParser;
-use dcql::parsers::PARSER;
+use dcql::parsers::{ResultFormat, CONFIG, PARSER};
+#[cfg(feature = "entrysets")]
+use dcql::{combinations::combinations, models::CredentialSetOption};
 
 #[cfg(target_arch = "wasm32")]
 #[global_allocator]
@@ -42,18 +53,64 @@ fn main() {
         return;
     };
     if credentials.is_empty() {
+        #[cfg(feature = "inlineissuance")]
+        if offer_issuance(&query, &credentials, provider_index, &WalletParser) {
+            return;
+        }
         return_error("parsing credentials failed");
         return;
     }
     // We should have only single credential presentation for now
-    let options = query.select_credentials(credentials.clone());
-    let Some(first) = options.first() else {
+    let mut options = query.select_credentials(credentials.clone());
+    // reading the clock would need a WASI import the host does not provide
+    let now = CONFIG.get().and_then(|a| a.timestamp);
+    query.rank(&mut options, now);
+    #[cfg(feature = "inlineissuance")]
+    if is_unsatisfied(&options)
+        && offer_issuance(&query, &credentials, provider_index, &WalletParser)
+    {
+        return;
+    }
+    #[cfg(feature = "entrysets")]
+    if entry_sets(&query, &options, provider_index) {
+        return;
+    }
+    // the first required credential set is presented, optional sets can be added
+    let Some(first) = options
+        .iter()
+        .find(|a| a.required)
+        .or_else(|| options.first())
+    else {
         return_error(&format!(
             "dcql 1 selection failed, {:?}/{:?}",
             query.credential_sets, query.credentials
         ));
         return;
     };
+    let disclaimer = disclaimer(
+        first.purpose.as_ref(),
+        query.verifier.as_deref(),
+        &WalletParser,
+    );
+    // the best ranked credential for every credential query of an optional set's
+    // first option
+    let optional = options
+        .iter()
+        .filter(|a| !a.required && a.index != first.index)
+        .filter_map(|a| {
+            let members = a
+                .set_options
+                .first()?
+                .iter()
+                .map(|set_option| {
+                    let disclosure = set_option.options.first()?;
+                    let attributes = presented_claims(&query, &set_option.id, disclosure);
+                    Some((set_option.id.as_str(), disclosure, attributes))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((a.index, members))
+        })
+        .collect::<Vec<_>>();
     let Some(first) = first.set_options.first() else {
         return_error("dcql 2 selection failed");
         return;
@@ -69,60 +126,154 @@ fn main() {
         ));
         return;
     }
+    let transaction_fields = query
+        .transaction_data
+        .iter()
+        .filter(|a| a.credential_ids.contains(&first_set.id))
+        .flat_map(|a| a.display_fields(WalletParser.locale().as_deref()))
+        .collect::<Vec<_>>();
+    let known_verifiers = CONFIG
+        .get()
+        .map(|a| a.known_verifiers.clone())
+        .unwrap_or_default();
     // Add all options we found
     for option in &first_set.options {
         let c = option.credential.clone();
-        let attributes = if option.claims_queries.is_empty() {
-            let cred_id = first_set.id.clone();
-            let Some(credentials) = &query.credentials else {
-                return_error("Invalid query");
-                return;
+        let attributes = presented_claims(&query, &first_set.id, option);
+        let paths = attributes.iter().map(|a| a.0.clone()).collect::<Vec<_>>();
+        let warning = query
+            .assess_risk(&first_set.id, &paths, &known_verifiers)
+            .warning(WalletParser.locale().as_deref());
+        // the entry on its own and with each optional credential set
+        for included in [None].into_iter().chain(optional.iter().map(Some)) {
+            let mut fields = transaction_fields.clone();
+            let mut selection = Selection {
+                provider_index,
+                claim_set: option.claim_set,
+                optional: vec![],
             };
-            let credentials = credentials.clone();
-            credentials
-                .into_iter()
-                .filter(|a| a.id == cred_id)
-                .flat_map(|a| {
-                    a.claims.unwrap_or(Vec::new()).into_iter().map(|a| {
-                        (
-                            a.path.clone(),
-                            a.path
-                                .clone()
-                                .into_iter()
-                                .map(|a| match a {
-                                    dcql::models::PointerPart::String(a) => a,
-                                    dcql::models::PointerPart::Index(i) => i.to_string(),
-                                    dcql::models::PointerPart::Null(_) => String::from("[]"),
-                                })
-                                .collect::<Vec<_>>()
-                                .join("/"),
-                        )
-                    })
-                })
-                .collect()
-        } else {
-            option
-                .claims_queries
-                .clone()
-                .into_iter()
-                .map(|first_query| {
-                    (
-                        first_query.path.clone(),
-                        first_query
-                            .path
-                            .clone()
-                            .into_iter()
-                            .map(|a| match a {
-                                dcql::models::PointerPart::String(a) => a,
-                                dcql::models::PointerPart::Index(i) => i.to_string(),
-                                dcql::models::PointerPart::Null(_) => String::from("[]"),
-                            })
-                            .collect::<Vec<_>>()
-                            .join("/"),
-                    )
+            if let Some((credential_set, members)) = included {
+                for (credential_query, disclosure, optional_attributes) in members {
+                    fields.extend(optional_fields(
+                        &disclosure.credential,
+                        optional_attributes,
+                        &WalletParser,
+                    ));
+                    selection.optional.push(OptionalSelection {
+                        credential_set: *credential_set,
+                        credential_query: credential_query.to_string(),
+                        credential_id: disclosure.credential.get_display_metadata(None).id,
+                        claim_set: disclosure.claim_set,
+                    });
+                }
+            }
+            select_credential(
+                c.clone(),
+                attributes.clone(),
+                &fields,
+                disclaimer.as_deref(),
+                warning.as_deref(),
+                &selection,
+                &WalletParser,
+            );
+        }
+    }
+}
+
+/// Presents credentials requested together, e.g. through several required
+/// credential sets, as entry sets. Returns `false` if no more than one credential
+/// is needed.
+#[cfg(feature = "entrysets")]
+fn entry_sets(query: &DcqlQuery, options: &[CredentialSetOption], provider_index: usize) -> bool {
+    let combinations = combinations(options);
+    if !combinations
+        .iter()
+        .any(|a| a.iter().filter(|a| a.required).count() > 1)
+    {
+        return false;
+    }
+    let locale = WalletParser.locale();
+    let known_verifiers = CONFIG
+        .get()
+        .map(|a| a.known_verifiers.clone())
+        .unwrap_or_default();
+    for combination in &combinations {
+        let purpose = combination
+            .first()
+            .and_then(|p| options.iter().find(|a| a.index == p.credential_set))
+            .and_then(|a| a.purpose.as_ref());
+        let disclaimer = disclaimer(purpose, query.verifier.as_deref(), &WalletParser);
+        let members = combination
+            .iter()
+            .map(|p| {
+                let attributes = presented_claims(query, p.credential_query, p.disclosure);
+                let paths = attributes.iter().map(|a| a.0.clone()).collect::<Vec<_>>();
+                SetMember {
+                    credential: p.disclosure.credential.clone(),
+                    extra_fields: query
+                        .transaction_data
+                        .iter()
+                        .filter(|a| a.credential_ids.iter().any(|a| a == p.credential_query))
+                        .flat_map(|a| a.display_fields(locale.as_deref()))
+                        .collect(),
+                    warning: query
+                        .assess_risk(p.credential_query, &paths, &known_verifiers)
+                        .warning(locale.as_deref()),
+                    attributes,
+                    selection: Selection {
+                        provider_index,
+                        claim_set: p.disclosure.claim_set,
+                        optional: vec![],
+                    },
+                }
+            })
+            .collect();
+        let set_id = serde_json::json!({
+            "provider_idx": provider_index,
+            "members": combination.iter().map(|p| p.to_json()).collect::<Vec<_>>()
+        });
+        select_credential_set(
+            &set_id.to_string(),
+            members,
+            disclaimer.as_deref(),
+            &WalletParser,
+        );
+    }
+    true
+}
+
+/// The claims presented with `option` for the credential query `credential_id`,
+/// all requested claims if no claim set applies.
+fn presented_claims(
+    query: &DcqlQuery,
+    credential_id: &str,
+    option: &Disclosure,
+) -> Vec<(Pointer, String)> {
+    let claims = if option.claims_queries.is_empty() {
+        query
+            .credentials
+            .iter()
+            .flatten()
+            .filter(|a| a.id == credential_id)
+            .flat_map(|a| a.claims.clone().unwrap_or_default())
+            .collect()
+    } else {
+        option.claims_queries.clone()
+    };
+    claims
+        .into_iter()
+        .map(|a| {
+            let label = a
+                .path
+                .iter()
+                .map(|a| match a {
+                    PointerPart::String(a) => a.clone(),
+                    PointerPart::Index(i) => i.to_string(),
+                    PointerPart::Null(_) => String::from("[]"),
                 })
                 .collect::<Vec<_>>()
-        };
-        select_credential(c.clone(), attributes.clone(), provider_index, &WalletParser);
-    }
+                .join("/");
+            (a.path, label)
+        })
+        .collect()
 }